@@ -0,0 +1,119 @@
+/* C API of the dat_cli algorithms. */
+
+#ifndef DAT_CLI_H
+#define DAT_CLI_H
+
+/* Generated from src/ffi.rs by cbindgen, run `DAT_CLI_BLESS=1 cargo test --test ffi` to update. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum DatCliStatus {
+  DAT_CLI_STATUS_OK = 0,
+  /**
+   * a pointer that must not be NULL was NULL
+   */
+  DAT_CLI_STATUS_NULL_POINTER = 1,
+  DAT_CLI_STATUS_INVALID_ARGUMENT = 2,
+  /**
+   * the algorithm failed unexpectedly, which is a bug
+   */
+  DAT_CLI_STATUS_INTERNAL = 3,
+} DatCliStatus;
+
+/**
+ * Algorithm for `dat_cli_daily_temperature`, which takes it as a plain integer so any value
+ * coming from C is safe to pass
+ */
+typedef enum DatCliDailyTemperatureMode {
+  DAT_CLI_DAILY_TEMPERATURE_MODE_STACK = 0,
+  DAT_CLI_DAILY_TEMPERATURE_MODE_REVERSE = 1,
+} DatCliDailyTemperatureMode;
+
+/**
+ * A task to schedule, running from `start` until `end`
+ */
+typedef struct DatCliTask {
+  int32_t start;
+  int32_t end;
+} DatCliTask;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Number of days to wait for a warmer temperature after each day, 0 if there is none. `mode` is
+ * one of `DatCliDailyTemperatureMode`. `days` must have room for `len` values.
+ *
+ * # Safety
+ * `temperatures` must point to `len` values and `days` to room for `len` values.
+ */
+enum DatCliStatus dat_cli_daily_temperature(const int32_t *temperatures,
+                                            size_t len,
+                                            uint32_t mode,
+                                            size_t *days,
+                                            char **error);
+
+/**
+ * Rotates the numbers to the right by `k` places. `rotated` must have room for `len` values.
+ *
+ * # Safety
+ * `numbers` must point to `len` values and `rotated` to room for `len` values.
+ */
+enum DatCliStatus dat_cli_rotate_right(const int32_t *numbers,
+                                       size_t len,
+                                       int32_t k,
+                                       int32_t *rotated,
+                                       char **error);
+
+/**
+ * Picks the most tasks that do not overlap. The indices of the picked tasks are written to
+ * `picked` in the order they run, which must have room for `len` indices, and their number to
+ * `picked_len`.
+ *
+ * # Safety
+ * `tasks` must point to `len` tasks, `picked` to room for `len` indices and `picked_len` to a
+ * single value.
+ */
+enum DatCliStatus dat_cli_schedule(const struct DatCliTask *tasks,
+                                   size_t len,
+                                   size_t *picked,
+                                   size_t *picked_len,
+                                   char **error);
+
+/**
+ * Runs a command from its arguments, e.g. `{"rotating-list", "-n", "1,2,3", "-k", "1"}`, as on
+ * the command line without the program name. Its result is written to `*result` as JSON, which the
+ * caller frees with `dat_cli_free_result`. File based commands read the file given with
+ * `--json-file-path`.
+ *
+ * # Safety
+ * `args` must point to `len` NUL terminated strings and `result` to a single pointer.
+ */
+enum DatCliStatus dat_cli_run(const char *const *args, size_t len, char **result, char **error);
+
+/**
+ * Frees a result returned by `dat_cli_run`. NULL is ignored.
+ *
+ * # Safety
+ * `result` must be NULL or a result returned by this library, which was not freed yet.
+ */
+void dat_cli_free_result(char *result);
+
+/**
+ * Frees an error message returned by any of the functions. NULL is ignored.
+ *
+ * # Safety
+ * `error` must be NULL or a message returned by this library, which was not freed yet.
+ */
+void dat_cli_free_error(char *error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DAT_CLI_H */
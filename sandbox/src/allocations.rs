@@ -0,0 +1,177 @@
+/*
+ * Heap usage of the command runs: peak live bytes, total bytes allocated and number of allocations.
+ *
+ * With the `track-allocations` feature, a global allocator wraps the system one and adds up every
+ * allocation of the threads running inside `track`. Without it, nothing is tracked and `track`
+ * returns None, so the builds that do not ask for it pay nothing.
+ */
+use serde::Serialize;
+use std::fmt;
+
+/// Whether the tracking allocator is built in
+pub const ENABLED: bool = cfg!(feature = "track-allocations");
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AllocationStats {
+    /// most bytes allocated at once, above what was allocated before the run
+    pub peak_bytes: u64,
+    /// bytes allocated over the whole run, whether they were freed or not
+    pub total_bytes: u64,
+    /// number of allocations, a reallocation counts as one
+    pub count: u64,
+}
+
+impl fmt::Display for AllocationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "peak {}, {} in {} allocations",
+            format_bytes(self.peak_bytes),
+            format_bytes(self.total_bytes),
+            self.count
+        )
+    }
+}
+
+/// Bytes in the largest unit that keeps them above 1, e.g. `1.5 KiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
+
+/**
+ * Runs `f` and returns what it returned along with the allocations it made on the current thread,
+ * None without the `track-allocations` feature. A run tracked inside another one counts for both.
+ */
+pub fn track<R>(f: impl FnOnce() -> R) -> (R, Option<AllocationStats>) {
+    #[cfg(feature = "track-allocations")]
+    {
+        let (result, stats) = tracking::track(f);
+        (result, Some(stats))
+    }
+    #[cfg(not(feature = "track-allocations"))]
+    {
+        (f(), None)
+    }
+}
+
+#[cfg(feature = "track-allocations")]
+mod tracking {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use super::AllocationStats;
+
+    /// The usage of a thread since its current `track` started
+    #[derive(Clone, Copy)]
+    struct Usage {
+        tracking: bool,
+        /// negative when the run frees more than it allocated
+        live: i64,
+        peak: i64,
+        total: u64,
+        count: u64,
+    }
+
+    const IDLE: Usage = Usage {
+        tracking: false,
+        live: 0,
+        peak: 0,
+        total: 0,
+        count: 0,
+    };
+
+    thread_local! {
+        // a plain Cell, so the allocator never allocates to reach it
+        static USAGE: Cell<Usage> = const { Cell::new(IDLE) };
+    }
+
+    fn record(allocated: usize, freed: usize) {
+        // the thread may be tearing down its locals already
+        let _ = USAGE.try_with(|usage| {
+            let mut current = usage.get();
+            if !current.tracking {
+                return;
+            }
+            current.live += allocated as i64 - freed as i64;
+            current.peak = current.peak.max(current.live);
+            if allocated > 0 {
+                current.total += allocated as u64;
+                current.count += 1;
+            }
+            usage.set(current);
+        });
+    }
+
+    pub struct TrackingAllocator;
+
+    unsafe impl GlobalAlloc for TrackingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                record(layout.size(), 0);
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                record(layout.size(), 0);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            record(0, layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                record(new_size, layout.size());
+            }
+            new_ptr
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+    pub fn track<R>(f: impl FnOnce() -> R) -> (R, AllocationStats) {
+        let outer = USAGE.with(|usage| {
+            usage.replace(Usage {
+                tracking: true,
+                ..IDLE
+            })
+        });
+        let result = f();
+        let inner = USAGE.with(|usage| usage.get());
+        // the outer run allocated whatever the inner one did, on top of what it had then
+        USAGE.with(|usage| {
+            usage.set(Usage {
+                tracking: outer.tracking,
+                live: outer.live + inner.live,
+                peak: outer.peak.max(outer.live + inner.peak),
+                total: outer.total + inner.total,
+                count: outer.count + inner.count,
+            })
+        });
+        let stats = AllocationStats {
+            peak_bytes: inner.peak.max(0) as u64,
+            total_bytes: inner.total,
+            count: inner.count,
+        };
+        (result, stats)
+    }
+}
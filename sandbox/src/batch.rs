@@ -0,0 +1,142 @@
+/*
+ * Runs a manifest of command invocations and compares their results against the expected ones.
+ *
+ * The manifest is either JSON or TOML (picked by the file extension), in the form of
+ *
+ * [datasets]
+ * jobs = { items = [{ title = "job1", deadline = 1 }] }
+ *
+ * [[cases]]
+ * name = "rotate right by 2"
+ * command = ["rotating-list", "--numbers", "1,2,3,4,5", "-k", "2"]
+ * expected = [4, 5, 1, 2, 3]
+ *
+ * [[cases]]
+ * name = "schedule from a file relative to the manifest"
+ * command = ["schedule", "--json-file-path", "schedule.json"]
+ * expected = { items = [] }
+ *
+ * [[cases]]
+ * name = "inline input"
+ * command = ["job-sequencing", "--json-file-path", "@input"]
+ * input = { items = [{ title = "job1", deadline = 1 }] }
+ * expected = { ... }
+ *
+ * Datasets and a case's own `input` (as `@input`) can be referenced like in the REPL.
+ */
+use log::{debug, info};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{utils, CommandContext, Datasets};
+
+#[derive(Deserialize, Debug)]
+struct BatchCase {
+    name: String,
+    command: Vec<String>,
+    #[serde(default)]
+    input: Option<Value>,
+    expected: Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct Manifest {
+    #[serde(default)]
+    datasets: HashMap<String, Value>,
+    cases: Vec<BatchCase>,
+}
+
+pub struct BatchSummary {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+impl BatchSummary {
+    pub fn is_success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+fn parse_manifest(path: &Path) -> Result<Manifest, Error> {
+    let content = fs::read_to_string(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        _ => serde_json::from_str(&content).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+    }
+}
+
+/**
+ * Runs a single case, returning the differences to the expected result (empty if it passed).
+ */
+fn run_case(
+    case: &BatchCase,
+    base_dir: &Path,
+    datasets: &Datasets,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let cmd = crate::parse_command(&case.command)?;
+
+    let mut case_datasets = datasets.clone();
+    if let Some(input) = &case.input {
+        case_datasets.insert("input".to_string(), input.clone());
+    }
+    let context = CommandContext {
+        datasets: &case_datasets,
+        // file paths in the manifest are relative to the manifest itself
+        base_dir: Some(base_dir),
+        ..CommandContext::default()
+    };
+
+    let actual = crate::execute_command(&cmd, &context)?;
+    debug!("{}: {}", case.name, actual);
+
+    Ok(utils::diff_json(&case.expected, &actual))
+}
+
+pub fn run_batch(manifest_path: &Path) -> Result<BatchSummary, Error> {
+    let manifest = parse_manifest(manifest_path)?;
+    let base_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    info!(
+        "Running {} cases from {}",
+        manifest.cases.len(),
+        manifest_path.display()
+    );
+
+    let mut summary = BatchSummary {
+        passed: 0,
+        failed: 0,
+    };
+    for case in manifest.cases.iter() {
+        match run_case(case, base_dir, &manifest.datasets) {
+            Ok(differences) if differences.is_empty() => {
+                summary.passed += 1;
+                println!("PASS {}", case.name);
+            }
+            Ok(differences) => {
+                summary.failed += 1;
+                println!("FAIL {}", case.name);
+                for difference in differences {
+                    println!("    {}", difference);
+                }
+            }
+            Err(e) => {
+                summary.failed += 1;
+                println!("FAIL {}", case.name);
+                println!("    error: {}", e);
+            }
+        }
+    }
+
+    println!(
+        "{} passed, {} failed, {} total",
+        summary.passed,
+        summary.failed,
+        manifest.cases.len()
+    );
+    Ok(summary)
+}
@@ -0,0 +1,417 @@
+/*
+ * Benchmarks every mode of a command over generated inputs of increasing size.
+ *
+ * Every command generates its own inputs and has sizes of its own to run when none are given, as
+ * the same sizes that take milliseconds for one algorithm would never finish for another.
+ *
+ * For each size, the command is run `iterations` times and the min, median and p95 timings are
+ * reported. The growth exponent is the slope of the median timing against the input size on a
+ * log-log scale, e.g. ~1 for linear and ~2 for quadratic algorithms.
+ *
+ * With `instrument`, every mode and size is run once more counting the operations of the
+ * algorithm, and each kind of operation gets a growth exponent of its own. The counts do not
+ * depend on the machine or its load, so they tell the growth apart more reliably than timings.
+ *
+ * With the `track-allocations` feature, every mode and size also gets a run tracking its heap
+ * usage.
+ *
+ * Random inputs are not always the hard ones, the random temperatures stay within a range that
+ * keeps the look ahead of `Reverse` short. With `worst_case` the commands that have a known worst
+ * case run on it instead.
+ */
+use log::debug;
+use serde::Serialize;
+use std::{
+    fmt,
+    io::{Error, ErrorKind},
+    time::Duration,
+};
+
+use crate::allocations::{self, format_bytes, AllocationStats};
+use crate::commands::{find_command, registry};
+use crate::generators::Rng;
+use crate::instrument::{self, Operation, OperationCounts};
+use crate::utils;
+
+/// Commands that can be benchmarked, those with a `Command::bench`
+pub fn bench_command_names() -> Vec<&'static str> {
+    registry()
+        .into_iter()
+        .filter(|command| command.bench().is_some())
+        .map(|command| command.name())
+        .collect()
+}
+
+/// Parses a size such as `1000` or `1e3`
+pub fn parse_size(s: &str) -> Result<usize, String> {
+    let size = s
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("Invalid size {}: {}", s, e))?;
+    if size < 1.0 || size.fract() != 0.0 {
+        return Err(format!("Invalid size {}: must be a positive integer", s));
+    }
+    Ok(size as usize)
+}
+
+#[derive(Serialize, Debug)]
+pub struct BenchSample {
+    pub mode: String,
+    pub size: usize,
+    pub min_ns: u128,
+    pub median_ns: u128,
+    pub p95_ns: u128,
+    /// counted in a run of its own, with `instrument`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operations: Option<OperationCounts>,
+    /// tracked in a run of its own, with the `track-allocations` feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allocations: Option<AllocationStats>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OperationGrowth {
+    pub operation: Operation,
+    /// slope of log(count) over log(size), None if there are fewer than two sizes
+    pub exponent: Option<f64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BenchGrowth {
+    pub mode: String,
+    /// slope of log(median) over log(size), None if there are fewer than two sizes
+    pub exponent: Option<f64>,
+    /// one per kind of operation the mode did, with `instrument`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<OperationGrowth>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BenchReport {
+    pub command: String,
+    pub iterations: usize,
+    pub seed: u64,
+    pub worst_case: bool,
+    pub samples: Vec<BenchSample>,
+    pub growth: Vec<BenchGrowth>,
+}
+
+/// Nearest-rank percentile of already sorted timings
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = ((p * sorted.len() as f64).ceil() as usize).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+/// Least squares slope of log(value) over log(size), for the median timings or operation counts
+fn growth_exponent(samples: &[(usize, u128)]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|&(size, value)| ((size as f64).ln(), (value.max(1) as f64).ln()))
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    Some(covariance / variance)
+}
+
+/// Benchmarks the command over the sizes, the default sizes of the command if None
+pub fn run_bench(
+    command: &str,
+    sizes: Option<&[usize]>,
+    iterations: usize,
+    seed: u64,
+    worst_case: bool,
+    instrument: bool,
+) -> Result<BenchReport, Error> {
+    let (modes, bench) = find_command(command)
+        .and_then(|found| Some((found.modes(), found.bench()?)))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown command: {}", command),
+            )
+        })?;
+    if worst_case && !bench.worst_case {
+        let worst_case_commands: Vec<&str> = registry()
+            .into_iter()
+            .filter(|command| command.bench().is_some_and(|bench| bench.worst_case))
+            .map(|command| command.name())
+            .collect();
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "No worst case input for {}, only for {}",
+                command,
+                worst_case_commands.join(", ")
+            ),
+        ));
+    }
+    let modes: Vec<&str> = modes.iter().map(|mode| mode.name).collect();
+    let sizes = sizes.unwrap_or(bench.sizes);
+    let iterations = iterations.max(1);
+
+    let mut samples = Vec::new();
+    // the algorithms log their inputs, which would drown the output and skew the timings
+    utils::quietly(|| -> Result<(), Error> {
+        for mode in modes.iter() {
+            for &size in sizes {
+                // the same seed per size, so every mode runs on the same input
+                let mut rng = Rng::new(seed ^ size as u64);
+                let run = (bench.generate)(mode, size, worst_case, &mut rng);
+                let mut timings = Vec::with_capacity(iterations);
+                for _ in 0..iterations {
+                    timings.push(run(mode)?);
+                }
+                timings.sort();
+                let operations = match instrument {
+                    true => {
+                        let (result, counts) = instrument::measure(|| run(mode));
+                        result?;
+                        Some(counts)
+                    }
+                    false => None,
+                };
+                let allocations = match allocations::ENABLED {
+                    true => {
+                        let (result, stats) = allocations::track(|| run(mode));
+                        result?;
+                        stats
+                    }
+                    false => None,
+                };
+                samples.push(BenchSample {
+                    mode: mode.to_string(),
+                    size,
+                    min_ns: timings[0].as_nanos(),
+                    median_ns: percentile(&timings, 0.5).as_nanos(),
+                    p95_ns: percentile(&timings, 0.95).as_nanos(),
+                    operations,
+                    allocations,
+                });
+            }
+        }
+        Ok(())
+    })?;
+
+    let growth = modes
+        .iter()
+        .map(|mode| {
+            let mode_samples: Vec<&BenchSample> = samples
+                .iter()
+                .filter(|sample| sample.mode == *mode)
+                .collect();
+            let timings: Vec<(usize, u128)> = mode_samples
+                .iter()
+                .map(|sample| (sample.size, sample.median_ns))
+                .collect();
+            let exponent = growth_exponent(&timings);
+            debug!("{} growth exponent: {:?}", mode, exponent);
+            let operations = Operation::ALL
+                .into_iter()
+                .filter_map(|operation| {
+                    let counts = mode_samples
+                        .iter()
+                        .map(|sample| {
+                            let count = sample.operations.as_ref()?.get(operation);
+                            Some((sample.size, count as u128))
+                        })
+                        .collect::<Option<Vec<(usize, u128)>>>()?;
+                    // the operations the mode never does have nothing to fit
+                    if counts.iter().all(|&(_, count)| count == 0) {
+                        return None;
+                    }
+                    Some(OperationGrowth {
+                        operation,
+                        exponent: growth_exponent(&counts),
+                    })
+                })
+                .collect();
+            BenchGrowth {
+                mode: mode.to_string(),
+                exponent,
+                operations,
+            }
+        })
+        .collect();
+
+    Ok(BenchReport {
+        command: command.to_string(),
+        iterations,
+        seed,
+        worst_case,
+        samples,
+        growth,
+    })
+}
+
+impl BenchReport {
+    fn is_instrumented(&self) -> bool {
+        self.samples
+            .iter()
+            .any(|sample| sample.operations.is_some())
+    }
+
+    /// The kinds of operations counted in any of the samples
+    fn counted_operations(&self) -> Vec<Operation> {
+        Operation::ALL
+            .into_iter()
+            .filter(|&operation| {
+                self.samples.iter().any(|sample| {
+                    sample
+                        .operations
+                        .as_ref()
+                        .is_some_and(|counts| counts.get(operation) > 0)
+                })
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("command,mode,size,iterations,min_ns,median_ns,p95_ns");
+        // a column per kind of operation, whether the mode did it or not
+        let operations: &[Operation] = match self.is_instrumented() {
+            true => &Operation::ALL,
+            false => &[],
+        };
+        for operation in operations.iter() {
+            csv.push_str(&format!(",{}", operation));
+        }
+        let allocations = self
+            .samples
+            .iter()
+            .any(|sample| sample.allocations.is_some());
+        if allocations {
+            csv.push_str(",peak_bytes,total_bytes,allocations");
+        }
+        csv.push('\n');
+        for sample in self.samples.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}",
+                self.command,
+                sample.mode,
+                sample.size,
+                self.iterations,
+                sample.min_ns,
+                sample.median_ns,
+                sample.p95_ns
+            ));
+            for &operation in operations.iter() {
+                let count = sample
+                    .operations
+                    .as_ref()
+                    .map_or(0, |counts| counts.get(operation));
+                csv.push_str(&format!(",{}", count));
+            }
+            if allocations {
+                let stats = sample.allocations.unwrap_or_default();
+                csv.push_str(&format!(
+                    ",{},{},{}",
+                    stats.peak_bytes, stats.total_bytes, stats.count
+                ));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} iterations, seed {}",
+            self.command, self.iterations, self.seed
+        )?;
+        match self.worst_case {
+            true => writeln!(f, ", worst case)")?,
+            false => writeln!(f, ")")?,
+        }
+        writeln!(
+            f,
+            "{:<10} {:>10} {:>14} {:>14} {:>14}",
+            "mode", "size", "min", "median", "p95"
+        )?;
+        for sample in self.samples.iter() {
+            writeln!(
+                f,
+                "{:<10} {:>10} {:>14} {:>14} {:>14}",
+                sample.mode,
+                sample.size,
+                format!("{:?}", Duration::from_nanos(sample.min_ns as u64)),
+                format!("{:?}", Duration::from_nanos(sample.median_ns as u64)),
+                format!("{:?}", Duration::from_nanos(sample.p95_ns as u64)),
+            )?;
+        }
+        let operations = self.counted_operations();
+        if !operations.is_empty() {
+            write!(f, "\n{:<10} {:>10}", "mode", "size")?;
+            for operation in operations.iter() {
+                write!(f, " {:>14}", operation.name())?;
+            }
+            writeln!(f)?;
+            for sample in self.samples.iter() {
+                write!(f, "{:<10} {:>10}", sample.mode, sample.size)?;
+                for &operation in operations.iter() {
+                    let count = sample
+                        .operations
+                        .as_ref()
+                        .map_or(0, |counts| counts.get(operation));
+                    write!(f, " {:>14}", count)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        let allocations = self
+            .samples
+            .iter()
+            .any(|sample| sample.allocations.is_some());
+        if allocations {
+            writeln!(
+                f,
+                "\n{:<10} {:>10} {:>14} {:>14} {:>14}",
+                "mode", "size", "peak heap", "allocated", "allocations"
+            )?;
+            for sample in self.samples.iter() {
+                let stats = sample.allocations.unwrap_or_default();
+                writeln!(
+                    f,
+                    "{:<10} {:>10} {:>14} {:>14} {:>14}",
+                    sample.mode,
+                    sample.size,
+                    format_bytes(stats.peak_bytes),
+                    format_bytes(stats.total_bytes),
+                    stats.count
+                )?;
+            }
+        }
+        if !operations.is_empty() || allocations {
+            writeln!(f)?;
+        }
+        for growth in self.growth.iter() {
+            match growth.exponent {
+                Some(exponent) => writeln!(f, "{}: ~O(n^{:.2})", growth.mode, exponent)?,
+                None => writeln!(f, "{}: not enough sizes to estimate growth", growth.mode)?,
+            }
+            for operation in growth.operations.iter() {
+                match operation.exponent {
+                    Some(exponent) => {
+                        writeln!(f, "  {}: ~O(n^{:.2})", operation.operation, exponent)?
+                    }
+                    None => writeln!(f, "  {}: not enough sizes", operation.operation)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
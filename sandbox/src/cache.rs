@@ -0,0 +1,212 @@
+/*
+ * Results of the expensive commands kept on disk, so running the same problem again returns right
+ * away. The cache is in `$XDG_CACHE_HOME/dat_cli/results` (or `~/.cache/dat_cli/results`), one
+ * JSON file per result.
+ *
+ * A result is keyed by the SHA-256 of the crate version, the name of the command, its arguments
+ * and its input. The input is hashed as parsed JSON with its keys sorted, so the same problem in
+ * another format, with other spacing or with its keys in another order shares the result. A new
+ * version of dat_cli never reads the results of an older one, `cache prune` removes them.
+ */
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{allocations::format_bytes, commands, input, utils, CommandContext, Invocation};
+
+/// Arguments that tell where the input is read from, rather than what it is
+const INPUT_ARGS: [&str; 2] = ["json_file_path", "input_format"];
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    version: String,
+    command: String,
+    result: Value,
+}
+
+/// The results removed by `Cache::prune`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Pruned {
+    pub removed: usize,
+    pub bytes: u64,
+}
+
+impl fmt::Display for Pruned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Removed {} cached results, {}",
+            self.removed,
+            format_bytes(self.bytes)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// The value with the keys of its objects sorted, whatever order they were read in
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, normalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(normalize).collect()),
+        value => value,
+    }
+}
+
+/**
+ * The key of the result of a command, None if the input is read from stdin, as reading it to hash
+ * it would leave nothing for the command.
+ *
+ * The input is read into the input type of the command, which for CSV decides whether a cell is a
+ * number or text, so the key does not depend on a guess. It is read here and again by the
+ * command, as the command only takes its arguments. Reading takes time linear in the input, little
+ * next to the commands worth caching.
+ */
+pub fn cache_key(cmd: &Invocation, context: &CommandContext) -> Result<Option<String>, io::Error> {
+    let input = match commands::json_file_path(&cmd.matches) {
+        Some(path) if input::is_stdin(path) => return Ok(None),
+        Some(path) => {
+            let format = commands::input_format(&cmd.matches);
+            match cmd.command.typed_input(path, format, context) {
+                Some(input) => input?,
+                None => context.parse_input::<Value>(path, format)?,
+            }
+        }
+        None => Value::Null,
+    };
+    let mut args = serde_json::Map::new();
+    // the arguments of the command only, the global flags end up in its matches too
+    for arg in cmd.command.args() {
+        let id = arg.get_id().as_str();
+        if INPUT_ARGS.contains(&id) {
+            continue;
+        }
+        let values: Vec<Value> = cmd
+            .matches
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|value| Value::String(value.to_string_lossy().to_string()))
+            .collect();
+        args.insert(id.to_string(), Value::Array(values));
+    }
+    let material = normalize(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "command": cmd.name(),
+        "args": args,
+        "input": input,
+    }));
+    let digest = Sha256::digest(material.to_string().as_bytes());
+    Ok(Some(
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+    ))
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Cache { dir: dir.into() }
+    }
+
+    /// The cache in the XDG cache directory, None if neither it nor $HOME is set
+    pub fn in_default_dir() -> Option<Self> {
+        utils::xdg_dir("XDG_CACHE_HOME", ".cache")
+            .map(|dir| Cache::new(dir.join("dat_cli").join("results")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// The cached result for the key, None if there is none or it cannot be read
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let entry: Entry = serde_json::from_str(&fs::read_to_string(self.path(key)).ok()?).ok()?;
+        (entry.version == env!("CARGO_PKG_VERSION")).then_some(entry.result)
+    }
+
+    /// Keeps the result under the key, replacing the file in one go so readers never see half of it
+    pub fn put(&self, key: &str, command: &str, result: &Value) -> Result<(), io::Error> {
+        fs::create_dir_all(&self.dir)?;
+        let entry = Entry {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            command: command.to_string(),
+            result: result.clone(),
+        };
+        let partial = self
+            .dir
+            .join(format!("{}.{}.partial", key, std::process::id()));
+        fs::write(&partial, serde_json::to_string(&entry)?)?;
+        fs::rename(&partial, self.path(key))
+    }
+
+    /**
+     * Removes the cached results older than `older_than`, or all of them if None. Results of other
+     * versions of dat_cli, which are never read again, and files left over by interrupted writes
+     * are removed either way.
+     */
+    pub fn prune(&self, older_than: Option<Duration>) -> Result<Pruned, io::Error> {
+        let mut pruned = Pruned::default();
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(pruned),
+            Err(e) => return Err(e),
+        };
+        let now = SystemTime::now();
+        for entry in entries {
+            let path = entry?.path();
+            // another prune, or a put renaming its file, may get to the file first
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let expired = match older_than {
+                None => true,
+                Some(older_than) => metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .is_some_and(|age| age >= older_than),
+            };
+            let current = path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_stem()
+                    .and_then(|key| key.to_str())
+                    .is_some_and(|key| self.get(key).is_some());
+            if expired || !current {
+                match fs::remove_file(&path) {
+                    Ok(()) => {
+                        pruned.removed += 1;
+                        pruned.bytes += metadata.len();
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(pruned)
+    }
+}
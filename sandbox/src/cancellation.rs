@@ -0,0 +1,160 @@
+/*
+ * Cooperative cancellation of long running commands. The command checks the token in its loops
+ * and stops with `Cancelled` once someone else cancelled it, like a timeout or Ctrl-C.
+ *
+ * sufficient-coverage-set checks it for every combination, schedule for every pick, job-sequencing
+ * for every job and daily-temperature for every day. rotating-list goes over its list a couple of
+ * times at most and runs to the end.
+ */
+use std::{
+    error::Error,
+    fmt, io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use log::warn;
+
+use crate::progress::Stage;
+
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancels the token, and every clone of it
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fails with `Cancelled` once the token is cancelled, to bail out of loops with `?`
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The error of a command that was cancelled before it finished
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// For the algorithms failing with `io::Error`, to bail out of their loops with `?` too
+impl From<Cancelled> for io::Error {
+    fn from(cancelled: Cancelled) -> Self {
+        io::Error::other(cancelled)
+    }
+}
+
+impl Cancelled {
+    /// Whether the error is `Cancelled`, on its own or inside an `io::Error`
+    pub fn caused(error: &(dyn Error + 'static)) -> bool {
+        error.is::<Cancelled>()
+            || error
+                .downcast_ref::<io::Error>()
+                .and_then(|e| e.get_ref())
+                .is_some_and(|e| e.is::<Cancelled>())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Timeout(Duration),
+    Interrupted,
+}
+
+/// The error of a run stopped before it finished, along with how far it got
+#[derive(Debug)]
+pub struct Stopped {
+    pub reason: StopReason,
+    /// the progress of the run when it stopped, empty for commands that do not report any
+    pub stages: Vec<Stage>,
+}
+
+impl fmt::Display for Stopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            StopReason::Timeout(timeout) => write!(f, "timed out after {:?}", timeout)?,
+            StopReason::Interrupted => write!(f, "interrupted")?,
+        }
+        if self.stages.is_empty() {
+            return write!(f, " before reporting any progress");
+        }
+        for stage in self.stages.iter() {
+            write!(f, "\n  {}", stage)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Stopped {}
+
+/// Parses a duration such as `30s`, `500ms`, `2m`, `1h` or `7d`, plain numbers being seconds
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid duration {}: {}", s, e))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        _ => {
+            return Err(format!(
+                "Invalid duration {}: the unit must be ms, s, m, h or d",
+                s
+            ))
+        }
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("Invalid duration {}: {}", s, e))
+}
+
+/**
+ * Makes the first Ctrl-C cancel the token instead of killing the process, so the command can stop
+ * and report how far it got, and the second one quit right away. Only the first token given is
+ * ever cancelled.
+ */
+pub fn cancel_on_interrupt(token: &CancellationToken) {
+    let token = token.clone();
+    let mut interrupted = false;
+    // the handler runs on a thread of its own, not inside the signal handler
+    let result = ctrlc::set_handler(move || {
+        if interrupted {
+            std::process::exit(130);
+        }
+        interrupted = true;
+        eprintln!("\nstopping, press Ctrl-C again to quit right away");
+        token.cancel();
+    });
+    match result {
+        Ok(()) | Err(ctrlc::Error::MultipleHandlers) => {}
+        Err(e) => warn!("Ctrl-C will quit right away, it cannot be handled: {}", e),
+    }
+}
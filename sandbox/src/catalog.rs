@@ -0,0 +1,134 @@
+/*
+ * The catalog of problems behind `list` and `describe`: what each command solves, the ways it can
+ * solve it at what cost, and an example that can be run to check the command still gives the
+ * documented result.
+ */
+use std::{error::Error, fmt::Write};
+
+use serde_json::Value;
+
+use crate::{
+    execute_command, parse_command, utils, Command, CommandContext, Datasets, INPUT_DATASET,
+};
+
+/// Width the problem statements and mode summaries are wrapped at
+const WRAP_WIDTH: usize = 80;
+
+/// Every command on a line, with its description
+pub fn list_commands(commands: &[&dyn Command]) -> String {
+    let width = commands
+        .iter()
+        .map(|command| command.name().len())
+        .max()
+        .unwrap_or(0);
+    let mut list = String::new();
+    for command in commands.iter() {
+        let _ = writeln!(
+            list,
+            "{:<width$}  {}",
+            command.name(),
+            command.description()
+        );
+    }
+    list
+}
+
+/// The words of `text` in lines of at most `WRAP_WIDTH` columns, each one after `indent` spaces
+fn wrap(text: &str, indent: usize) -> String {
+    let mut wrapped = String::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && indent + line.len() + 1 + word.len() > WRAP_WIDTH {
+            let _ = writeln!(wrapped, "{:indent$}{}", "", line);
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        let _ = writeln!(wrapped, "{:indent$}{}", "", line);
+    }
+    wrapped
+}
+
+fn indent(text: &str, indent: usize) -> String {
+    text.lines()
+        .map(|line| format!("{:indent$}{}\n", "", line))
+        .collect()
+}
+
+/// The problem, modes and example of a command, as text for the terminal
+pub fn describe_text(command: &dyn Command) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "{}: {}\n", command.name(), command.description());
+    text.push_str(&wrap(command.problem(), 2));
+
+    text.push_str("\nModes:\n");
+    let modes = command.modes();
+    let name_width = modes.iter().map(|mode| mode.name.len()).max().unwrap_or(0);
+    for mode in modes.iter() {
+        let _ = writeln!(
+            text,
+            "  {:<name_width$}  time {}, space {}",
+            mode.name, mode.time, mode.space
+        );
+        text.push_str(&wrap(mode.summary, name_width + 4));
+    }
+
+    let example = command.example();
+    text.push_str("\nExample:\n");
+    let mut command_line = vec!["dat_cli", command.name()];
+    command_line.extend(example.args);
+    if command.takes_input() {
+        command_line.extend(["--json-file-path", "input.json"]);
+    }
+    let _ = writeln!(text, "  {}", command_line.join(" "));
+    if let Some(input) = command.input_example() {
+        text.push_str("  with input.json:\n");
+        text.push_str(&indent(input, 4));
+    }
+    text.push_str("  gives:\n");
+    text.push_str(&indent(example.output, 4));
+    text
+}
+
+/// The result of running the example of a command, next to the one it documents
+pub struct ExampleRun {
+    pub expected: Value,
+    pub actual: Value,
+}
+
+impl ExampleRun {
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+
+    /// The lines that differ between the expected and the actual result, empty if it passed
+    pub fn diff(&self) -> Vec<String> {
+        let pretty = |value| serde_json::to_string_pretty(value).unwrap_or_default();
+        utils::diff_lines(&pretty(&self.expected), &pretty(&self.actual))
+    }
+}
+
+/// Runs the example of a command, giving file based commands their `input_example` as input
+pub fn run_example(command: &'static dyn Command) -> Result<ExampleRun, Box<dyn Error>> {
+    let example = command.example();
+    let mut command_line = vec![command.name().to_string()];
+    command_line.extend(example.args.iter().map(|arg| arg.to_string()));
+    let mut datasets = Datasets::new();
+    if let Some(input) = command.input_example() {
+        datasets.insert(INPUT_DATASET.to_string(), serde_json::from_str(input)?);
+        command_line.push(format!("--json-file-path=@{}", INPUT_DATASET));
+    }
+    let invocation = parse_command(command_line)?;
+    let context = CommandContext {
+        datasets: &datasets,
+        ..CommandContext::default()
+    };
+    Ok(ExampleRun {
+        expected: serde_json::from_str(example.output)?,
+        actual: execute_command(&invocation, &context)?,
+    })
+}
@@ -1,4 +1,361 @@
+use clap::{value_parser, Arg, ArgMatches};
+use serde_json::json;
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use crate::cancellation::CancellationToken;
+use crate::generators::Rng;
+use crate::input::{self, InputFormat};
+use crate::progress::Progress;
+use crate::trace::Tracer;
+use crate::utils::{self, Datasets};
+
+/// Datasets of the contexts that have none
+static NO_DATASETS: LazyLock<Datasets> = LazyLock::new(Datasets::new);
+
+/**
+ * Everything a command and the algorithm behind it can use while running, besides its own
+ * arguments. The default context has no datasets, is never cancelled, traces nothing and shows no
+ * progress, for running an algorithm on its own.
+ */
+pub struct CommandContext<'a> {
+    pub datasets: &'a Datasets,
+    /// directory that relative JSON file paths are resolved against, the working directory if None
+    pub base_dir: Option<&'a Path>,
+    /// checked by long running commands, which stop early once it is cancelled
+    pub cancellation: CancellationToken,
+    /// receives the events of the algorithms, for `--trace`
+    pub tracer: Tracer<'a>,
+    /// counted by long running commands, to show how far they got
+    pub progress: Option<&'a Progress>,
+}
+
+impl Default for CommandContext<'_> {
+    fn default() -> Self {
+        CommandContext {
+            datasets: &NO_DATASETS,
+            base_dir: None,
+            cancellation: CancellationToken::new(),
+            tracer: Tracer::none(),
+            progress: None,
+        }
+    }
+}
+
+impl CommandContext<'_> {
+    /// Parses the input, either from a `@name` dataset, from stdin for `-` or from a file
+    pub fn parse_input<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &PathBuf,
+        format: Option<InputFormat>,
+    ) -> Result<T, io::Error> {
+        match self.base_dir {
+            Some(base_dir)
+                if utils::dataset_name(path).is_none()
+                    && !input::is_stdin(path)
+                    && path.is_relative() =>
+            {
+                utils::parse_input(&base_dir.join(path), format, self.datasets)
+            }
+            _ => utils::parse_input(path, format, self.datasets),
+        }
+    }
+}
+
+/// One way a command solves its problem, with its cost in terms of the sizes its summary names
+pub struct Mode {
+    /// as given to the mode argument, for commands that have one
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub time: &'static str,
+    pub space: &'static str,
+}
+
+/// A run of a command along with the result it gives, checked by `describe --run-example`
+pub struct Example {
+    /// arguments after the name of the command, file based commands read `input_example` on top
+    pub args: &'static [&'static str],
+    /// the expected result, as JSON
+    pub output: &'static str,
+}
+
+/// Runs a generated input once in the given mode, returning how long the algorithm itself took
+pub type BenchRun = Box<dyn Fn(&str) -> Result<Duration, io::Error>>;
+
+/// How `bench` exercises a command over inputs of increasing size
+pub struct Bench {
+    /// sizes run when none are given, small enough for every mode to finish in seconds
+    pub sizes: &'static [usize],
+    /// whether `generate` has a worst case input, for `--worst-case`
+    pub worst_case: bool,
+    /// generates the input of a mode and size, the worst case one if asked for
+    pub generate: fn(mode: &str, size: usize, worst_case: bool, rng: &mut Rng) -> BenchRun,
+}
+
+/// How `gen` generates random inputs for a command
+pub struct Generator {
+    /// one line description of the input, shown in the help of `gen`
+    pub about: &'static str,
+    /// arguments of the `gen` subcommand, the size and shape of the input
+    pub args: Vec<Arg>,
+    /// generates an input from the parsed `args`, in the shape the command reads it
+    pub generate: fn(matches: &ArgMatches, rng: &mut Rng) -> Result<String, io::Error>,
+}
+
+/// Reads the input as a `T` and back into JSON, for `Command::typed_input`
+pub fn read_as<T: serde::de::DeserializeOwned + serde::Serialize>(
+    path: &PathBuf,
+    format: Option<InputFormat>,
+    context: &CommandContext,
+) -> Result<serde_json::Value, io::Error> {
+    let input: T = context.parse_input(path, format)?;
+    serde_json::to_value(input).map_err(io::Error::other)
+}
+
+/// Times `f`, for the runs of `Bench::generate`
+pub fn timed<T, E>(f: impl FnOnce() -> Result<T, E>) -> Result<Duration, E> {
+    let time_elapsed = Instant::now();
+    f()?;
+    Ok(time_elapsed.elapsed())
+}
+
+/**
+ * A problem that dat_cli can solve. The clap subcommand is generated from the name, description
+ * and arguments, and `run` gets the parsed arguments back.
+ */
+pub trait Command: Sync {
+    /// Name of the subcommand, as typed on the command line
+    fn name(&self) -> &'static str;
+
+    /// One line description, shown in the help
+    fn description(&self) -> &'static str;
+
+    /// Full statement of the problem, shown by `describe`
+    fn problem(&self) -> &'static str;
+
+    /// The ways the problem can be solved, one per value of the mode argument if there is one
+    fn modes(&self) -> Vec<Mode>;
+
+    /// A run of the command along with its expected result
+    fn example(&self) -> Example;
+
+    fn args(&self) -> Vec<Arg>;
+
+    /// Example of the JSON input file, for commands that read one
+    fn input_example(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// JSON Schema of the input file, derived from the type it is deserialized into
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// The input file read into the type the command takes, as JSON, for commands that read one.
+    /// Unlike reading it straight into JSON, the CSV cells get the types of the fields they go in
+    fn typed_input(
+        &self,
+        _path: &PathBuf,
+        _format: Option<InputFormat>,
+        _context: &CommandContext,
+    ) -> Option<Result<serde_json::Value, io::Error>> {
+        None
+    }
+
+    /// JSON Schema of the result, derived from its type
+    fn output_schema(&self) -> serde_json::Value;
+
+    /// Whether the command reads a JSON input file
+    fn takes_input(&self) -> bool {
+        self.args()
+            .iter()
+            .any(|arg| arg.get_id() == "json_file_path")
+    }
+
+    /// Whether results are kept in the result cache, by default for the file based commands, whose
+    /// inputs can be big enough to take long to solve
+    fn cacheable(&self) -> bool {
+        self.takes_input()
+    }
+
+    /// How the command is benchmarked, None if `bench` does not support it
+    fn bench(&self) -> Option<Bench> {
+        None
+    }
+
+    /// How `gen` generates inputs for the command, None if it does not support it
+    fn generator(&self) -> Option<Generator> {
+        None
+    }
+
+    /// Runs the command and returns its result as JSON
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        context: &CommandContext,
+    ) -> Result<serde_json::Value, Box<dyn Error>>;
+
+    /// Reports the result of a run from the command line, on stdout as the logs are on stderr
+    fn report(&self, result: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+        println!("{}", result);
+        Ok(())
+    }
+
+    fn clap_command(&self) -> clap::Command {
+        let command = clap::Command::new(self.name())
+            .about(self.description())
+            .args(self.args());
+        match self.input_example() {
+            Some(example) => {
+                command.after_help(format!("Input JSON file in the form of\n{}", example))
+            }
+            None => command,
+        }
+    }
+}
+
+/// The `--json-file-path` argument shared by file based commands
+pub fn json_file_arg() -> Arg {
+    Arg::new("json_file_path")
+        .short('j')
+        .long("json-file-path")
+        .value_name("JSON_FILE_PATH")
+        .required(true)
+        .value_parser(value_parser!(PathBuf))
+        .help("Path to the JSON, YAML, TOML or CSV input file, `-` for stdin or `@name` for a loaded dataset")
+}
+
+/// The `--format` argument that goes along with `json_file_arg`, for files without a telling
+/// extension and for stdin
+pub fn input_format_arg() -> Arg {
+    Arg::new("input_format")
+        .long("format")
+        .value_name("FORMAT")
+        .value_parser(InputFormat::NAMES)
+        .help("format of the input file, instead of going by its extension: either \"json\", \"yaml\", \"toml\" or \"csv\"")
+}
+
+/// The command and its arguments as JSON, for the machine readable listings
+pub fn describe_command(command: &dyn Command) -> serde_json::Value {
+    let args: Vec<serde_json::Value> = command
+        .args()
+        .iter()
+        // the input is described separately
+        .filter(|arg| arg.get_id() != "json_file_path" && arg.get_id() != "input_format")
+        .map(|arg| {
+            json!({
+                "name": arg.get_id().as_str(),
+                "help": arg.get_help().map(|help| help.to_string()),
+                "required": arg.is_required_set(),
+                "default": arg.get_default_values().first().map(|value| value.to_string_lossy()),
+                "values": arg
+                    .get_possible_values()
+                    .iter()
+                    .map(|value| value.get_name())
+                    .collect::<Vec<&str>>(),
+            })
+        })
+        .collect();
+    let modes: Vec<serde_json::Value> = command
+        .modes()
+        .iter()
+        .map(|mode| {
+            json!({
+                "name": mode.name,
+                "summary": mode.summary,
+                "time": mode.time,
+                "space": mode.space,
+            })
+        })
+        .collect();
+    let example = command.example();
+    json!({
+        "name": command.name(),
+        "description": command.description(),
+        "problem": command.problem(),
+        "takes_input": command.takes_input(),
+        "input_example": command
+            .input_example()
+            .and_then(|example| serde_json::from_str::<serde_json::Value>(example).ok()),
+        "args": args,
+        "modes": modes,
+        "example": {
+            "args": example.args,
+            "output": serde_json::from_str::<serde_json::Value>(example.output).ok(),
+        },
+    })
+}
+
+/**
+ * The schema of a result that is replaced by a chart with `--render`, allowing either of them.
+ */
+pub fn or_rendered_chart(mut schema: serde_json::Value) -> serde_json::Value {
+    let document = schema.as_object_mut().unwrap();
+    // everything but what belongs to the document describes the result
+    let keys: Vec<String> = document
+        .keys()
+        .filter(|key| !["$schema", "title", "$defs"].contains(&key.as_str()))
+        .cloned()
+        .collect();
+    let mut result = serde_json::Map::new();
+    for key in keys {
+        let value = document.remove(&key).unwrap();
+        result.insert(key, value);
+    }
+    document.insert(
+        "anyOf".to_string(),
+        json!([result, { "type": "string", "description": "the chart drawn with --render" }]),
+    );
+    schema
+}
+
+pub fn json_file_path(matches: &ArgMatches) -> Option<&PathBuf> {
+    matches
+        .try_get_one::<PathBuf>("json_file_path")
+        .ok()
+        .flatten()
+}
+
+/// The format given with `--format`, None to go by the extension
+pub fn input_format(matches: &ArgMatches) -> Option<InputFormat> {
+    matches
+        .try_get_one::<String>("input_format")
+        .ok()
+        .flatten()
+        .and_then(|name| InputFormat::from_name(name))
+}
+
 pub mod daily_temperature;
+pub mod job_sequencing;
 pub mod rotating_list;
 pub mod schedule;
 pub mod sufficient_coverage_set;
+
+/**
+ * A command, registered from its own module with `inventory::submit!`. A new problem only needs a
+ * module submitting its `COMMAND`, nothing else lists the commands.
+ */
+pub struct Registration(pub &'static dyn Command);
+
+inventory::collect!(Registration);
+
+/// Every registered command, in the order of their names as they are shown in the help
+pub fn registry() -> Vec<&'static dyn Command> {
+    let mut commands: Vec<&'static dyn Command> = inventory::iter::<Registration>
+        .into_iter()
+        .map(|registration| registration.0)
+        .collect();
+    commands.sort_by_key(|command| command.name());
+    commands
+}
+
+pub fn find_command(name: &str) -> Option<&'static dyn Command> {
+    registry()
+        .into_iter()
+        .find(|command| command.name() == name)
+}
@@ -1,6 +1,20 @@
-/**
+/*
  * Given an array of integers temperatures represents the daily temperatures, return an array answer such that answer[i] is the number of days you have to wait after the ith day to get a warmer temperature. If there is no future day for which this is possible, keep answer[i] == 0 instead.
  */
+use clap::{value_parser, Arg, ArgMatches};
+use log::info;
+use std::{
+    error::Error,
+    io::{self, ErrorKind},
+};
+
+use crate::commands::{
+    timed, Bench, Command, CommandContext, Example, Generator, Mode, Registration,
+};
+use crate::generators;
+use crate::instrument::{self, Operation};
+use crate::schema::schema_for;
+use crate::trace::TraceEvent;
 
 pub enum DailyTemperatureMode {
     //
@@ -9,41 +23,76 @@ pub enum DailyTemperatureMode {
 }
 
 impl DailyTemperatureMode {
-    fn from_str(s: &str) -> Self {
+    pub const NAMES: [&'static str; 2] = ["Stack", "Reverse"];
+
+    fn from_str(s: &str) -> Result<Self, io::Error> {
         match s {
-            "Stack" => DailyTemperatureMode::Stack,
-            "Reverse" => DailyTemperatureMode::Reverse,
-            _ => panic!("Invalid mode"),
+            "Stack" => Ok(DailyTemperatureMode::Stack),
+            "Reverse" => Ok(DailyTemperatureMode::Reverse),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid mode: {}", s),
+            )),
         }
     }
 }
 
-pub fn get_daily_temperature(temperatures: &Vec<i32>, mode: &String) -> Result<Vec<usize>, String> {
-    match DailyTemperatureMode::from_str(&mode) {
+/// The days to wait for each day, sending the stack operations and comparisons to the tracer
+pub fn get_daily_temperature(
+    temperatures: &[i32],
+    mode: &str,
+    context: &CommandContext,
+) -> Result<Vec<usize>, io::Error> {
+    let parsed_mode = DailyTemperatureMode::from_str(mode)?;
+    let tracer = context.tracer;
+    tracer.emit(|| TraceEvent::TemperatureScan {
+        temperatures: temperatures.to_vec(),
+        mode: mode.to_string(),
+    });
+    match parsed_mode {
         DailyTemperatureMode::Stack => {
             let mut stack: Vec<usize> = Vec::new();
             let mut result: Vec<usize> = vec![0; temperatures.len()];
+            instrument::count(Operation::Allocation, 2);
             // iterate through all temperatures
             for (i, &temp) in temperatures.iter().enumerate() {
+                context.cancellation.check()?;
                 // if the stack is not empty and the current temperature is greater than the temperature at the top of the stack
-                while stack.len() > 0 && &temp > &temperatures[*stack.last().unwrap()] {
+                while !stack.is_empty() && {
+                    instrument::count(Operation::Comparison, 1);
+                    temp > temperatures[*stack.last().unwrap()]
+                } {
                     // calculate the difference between the current index and the index at the top of the stack
                     let index = stack.pop().unwrap();
+                    instrument::count(Operation::StackPop, 1);
+                    tracer.emit(|| TraceEvent::StackPop {
+                        day: index,
+                        warmer_day: i,
+                    });
                     result[index] = i - index;
                 }
                 // push the current index onto the stack
                 stack.push(i);
+                instrument::count(Operation::StackPush, 1);
+                tracer.emit(|| TraceEvent::StackPush { day: i });
             }
             Ok(result)
         }
         DailyTemperatureMode::Reverse => {
             let mut result: Vec<usize> = vec![0; temperatures.len()];
+            instrument::count(Operation::Allocation, 1);
             // iterate through all temperatures in reverse order
             for i in (0..temperatures.len()).rev() {
+                context.cancellation.check()?;
                 // create a pointer to the following temperature
                 let mut current_pointer = i + 1;
                 // as long as the pointer is within the bounds of the array
                 while current_pointer < temperatures.len() {
+                    tracer.emit(|| TraceEvent::Compare {
+                        day: i,
+                        with: current_pointer,
+                    });
+                    instrument::count(Operation::Comparison, 1);
                     // if the pointer temperature is greater than the temperature at the evaluating index
                     if temperatures[current_pointer] > temperatures[i] {
                         // calculate the difference between the current index and the index
@@ -56,8 +105,141 @@ pub fn get_daily_temperature(temperatures: &Vec<i32>, mode: &String) -> Result<V
                     }
                     current_pointer += 1;
                 }
+                tracer.emit(|| TraceEvent::Answer {
+                    day: i,
+                    days: result[i],
+                });
             }
             Ok(result)
         }
     }
 }
+
+pub struct DailyTemperatureCommand;
+
+pub static COMMAND: DailyTemperatureCommand = DailyTemperatureCommand;
+
+inventory::submit!(Registration(&COMMAND));
+
+impl Command for DailyTemperatureCommand {
+    fn name(&self) -> &'static str {
+        "daily-temperature"
+    }
+
+    fn description(&self) -> &'static str {
+        "Number of days to wait for a warmer temperature"
+    }
+
+    fn problem(&self) -> &'static str {
+        "Given the daily temperatures, find for each day the number of days to wait until a warmer \
+         temperature, or 0 if no later day is warmer."
+    }
+
+    fn modes(&self) -> Vec<Mode> {
+        vec![
+            Mode {
+                name: "Stack",
+                summary: "Keeps the n days still waiting for a warmer one on a stack, and answers \
+                          them as the warmer day comes",
+                time: "O(n)",
+                space: "O(n)",
+            },
+            Mode {
+                name: "Reverse",
+                summary: "Goes from the last of the n days to the first, looking ahead for a \
+                          warmer day and stopping at the days that have none",
+                time: "O(n^2)",
+                space: "O(1) besides the answer",
+            },
+        ]
+    }
+
+    fn example(&self) -> Example {
+        Example {
+            args: &[
+                "--temperatures",
+                "73,74,75,71,69,72,76,73",
+                "--mode",
+                "Stack",
+            ],
+            output: "[1, 1, 4, 2, 1, 1, 0, 0]",
+        }
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        vec![
+            Arg::new("temperatures")
+                .short('t')
+                .long("temperatures")
+                .value_name("TEMPERATURES")
+                .required(true)
+                .value_delimiter(',')
+                .value_parser(value_parser!(i32))
+                .help("numbers containing the temperatures separated by commas")
+                .long_help(
+                    "numbers containing the temperatures separated by commas\n33, 74, 75, 71, 69, 72, 76, 73",
+                ),
+            Arg::new("mode")
+                .short('m')
+                .long("mode")
+                .value_name("MODE")
+                .required(true)
+                .value_parser(DailyTemperatureMode::NAMES)
+                .help("algorithm mode: either \"Stack\" or \"Reverse\""),
+        ]
+    }
+
+    fn output_schema(&self) -> serde_json::Value {
+        schema_for::<Vec<usize>>("daily-temperature result")
+    }
+
+    fn generator(&self) -> Option<Generator> {
+        Some(Generator {
+            about:
+                "Temperatures separated by commas, as passed to `daily-temperature --temperatures`",
+            args: vec![generators::length_arg("number of temperatures")],
+            generate: |matches, rng| {
+                let length = *matches.get_one::<usize>("length").unwrap();
+                Ok(generators::join_numbers(&generators::temperatures(
+                    rng, length,
+                )))
+            },
+        })
+    }
+
+    fn bench(&self) -> Option<Bench> {
+        Some(Bench {
+            sizes: &[1_000, 10_000],
+            worst_case: true,
+            generate: |_, size, worst_case, rng| {
+                let temperatures = match worst_case {
+                    true => generators::falling_temperatures(size),
+                    false => generators::temperatures(rng, size),
+                };
+                Box::new(move |mode| {
+                    timed(|| get_daily_temperature(&temperatures, mode, &CommandContext::default()))
+                })
+            },
+        })
+    }
+
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        context: &CommandContext,
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let temperatures: Vec<i32> = matches
+            .get_many::<i32>("temperatures")
+            .unwrap()
+            .copied()
+            .collect();
+        let mode = matches.get_one::<String>("mode").unwrap();
+        // Implement the daily temperature algorithm here
+        info!(
+            "Finding daily temperature from {:?} using {:?} method",
+            temperatures, mode
+        );
+        let result = get_daily_temperature(&temperatures, mode, context)?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,382 @@
+/*
+ * Given a set of jobs with a release time, a processing time and a deadline, find an order to
+ * run them on a single machine.
+ *
+ * edf:    Earliest-Deadline-First. Whenever the machine is free, run the released job with the
+ *         earliest deadline. Minimizes the maximum lateness when all jobs are released at the same
+ *         time (and for unit jobs with integer release times).
+ * profit: Unit-job sequencing with deadlines. Every job takes one time unit and only earns its
+ *         profit if it finishes by its deadline. Greedily keep the most profitable jobs as long as
+ *         the kept set can still be run on time.
+ */
+use clap::{Arg, ArgAction, ArgMatches};
+use log::debug;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::cancellation::CancellationToken;
+use crate::commands::{
+    input_format, input_format_arg, json_file_arg, json_file_path, read_as, timed, Bench, Command,
+    CommandContext, Example, Generator, Mode, Registration,
+};
+use crate::generators;
+use crate::input::InputFormat;
+use crate::instrument::{self, Operation};
+use crate::schema::schema_for;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
+
+pub enum JobSequencingMode {
+    Edf,
+    Profit,
+}
+
+impl JobSequencingMode {
+    pub const NAMES: [&'static str; 2] = ["edf", "profit"];
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "edf" => Ok(JobSequencingMode::Edf),
+            "profit" => Ok(JobSequencingMode::Profit),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid mode: {}", s),
+            )),
+        }
+    }
+}
+
+fn default_processing_time() -> i32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Job {
+    title: String,
+    #[serde(default)]
+    release: i32,
+    #[serde(default = "default_processing_time")]
+    processing_time: i32,
+    deadline: i32,
+    #[serde(default)]
+    profit: i32,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct JobSchedule {
+    items: Vec<Job>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct SequencedJob {
+    title: String,
+    start: i32,
+    end: i32,
+    deadline: i32,
+    lateness: i32,
+    profit: i32,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct JobSequence {
+    items: Vec<SequencedJob>,
+    rejected: Vec<Job>,
+    max_lateness: i32,
+    total_profit: i32,
+}
+
+fn overflow(job: &Job) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("{} would end past the largest supported time", job.title),
+    )
+}
+
+/**
+ * Runs the given jobs in Earliest-Deadline-First order, idling until the next release whenever
+ * nothing is ready.
+ */
+fn earliest_deadline_first(
+    jobs: &[Job],
+    cancellation: &CancellationToken,
+) -> Result<Vec<SequencedJob>, Error> {
+    let mut by_release: Vec<usize> = (0..jobs.len()).collect();
+    by_release.sort_by(|&a, &b| {
+        instrument::count(Operation::Comparison, 1);
+        jobs[a].release.cmp(&jobs[b].release)
+    });
+
+    let mut ready = BinaryHeap::new();
+    let mut sequence = Vec::with_capacity(jobs.len());
+    instrument::count(Operation::Allocation, 3);
+    let mut next_release = 0;
+    let mut time = i32::MIN;
+
+    while sequence.len() < jobs.len() {
+        cancellation.check()?;
+        // if nothing is ready, jump ahead to the next release
+        if ready.is_empty() {
+            time = time.max(jobs[by_release[next_release]].release);
+        }
+        // move all released jobs into the ready queue, ordered by deadline then input order
+        while next_release < by_release.len() && jobs[by_release[next_release]].release <= time {
+            let i = by_release[next_release];
+            ready.push(Reverse((jobs[i].deadline, i)));
+            next_release += 1;
+        }
+
+        let Reverse((_, i)) = ready.pop().unwrap();
+        let job = &jobs[i];
+        let start = time.max(job.release);
+        let end = start
+            .checked_add(job.processing_time)
+            .ok_or_else(|| overflow(job))?;
+        debug!("picked {} to run from {} to {}", job.title, start, end);
+        instrument::count(Operation::Clone, 1);
+        sequence.push(SequencedJob {
+            title: job.title.clone(),
+            start,
+            end,
+            deadline: job.deadline,
+            lateness: end.checked_sub(job.deadline).ok_or_else(|| overflow(job))?,
+            profit: job.profit,
+        });
+        time = end;
+    }
+
+    Ok(sequence)
+}
+
+fn sequence_by_profit(
+    jobs: &[Job],
+    cancellation: &CancellationToken,
+) -> Result<(Vec<SequencedJob>, Vec<Job>), Error> {
+    if let Some(job) = jobs.iter().find(|job| job.processing_time != 1) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "profit mode only supports unit jobs, {} has a processing time of {}",
+                job.title, job.processing_time
+            ),
+        ));
+    }
+
+    let mut by_profit = jobs.to_vec();
+    instrument::count(Operation::Clone, jobs.len() as u64);
+    instrument::count(Operation::Allocation, 1);
+    // stable sort, so ties keep their input order
+    by_profit.sort_by(|a, b| {
+        instrument::count(Operation::Comparison, 1);
+        b.profit.cmp(&a.profit)
+    });
+
+    let mut accepted: Vec<Job> = Vec::new();
+    let mut rejected: Vec<Job> = Vec::new();
+    for job in by_profit {
+        accepted.push(job);
+        // EDF is optimal for unit jobs, so the set is feasible iff EDF runs it without lateness
+        if earliest_deadline_first(&accepted, cancellation)?
+            .iter()
+            .any(|item| item.lateness > 0)
+        {
+            let job = accepted.pop().unwrap();
+            debug!("rejected {} as it cannot be finished on time", job.title);
+            rejected.push(job);
+        }
+    }
+
+    Ok((earliest_deadline_first(&accepted, cancellation)?, rejected))
+}
+
+/// The jobs in the order the mode runs them, failing with an `io::Error` wrapping `Cancelled` once
+/// the context is cancelled
+pub fn sequence_jobs(
+    schedule: &JobSchedule,
+    mode: &str,
+    context: &CommandContext,
+) -> Result<JobSequence, Error> {
+    debug!("Extracted jobs: {:?}", schedule);
+    if let Some(job) = schedule.items.iter().find(|job| job.processing_time < 1) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} has a processing time of {}, it must be at least 1",
+                job.title, job.processing_time
+            ),
+        ));
+    }
+    let (items, rejected) = match JobSequencingMode::from_str(mode)? {
+        JobSequencingMode::Edf => (
+            earliest_deadline_first(&schedule.items, &context.cancellation)?,
+            vec![],
+        ),
+        JobSequencingMode::Profit => sequence_by_profit(&schedule.items, &context.cancellation)?,
+    };
+
+    let max_lateness = items.iter().map(|item| item.lateness).max().unwrap_or(0);
+    // a job only earns its profit if it finishes by its deadline
+    let total_profit = items
+        .iter()
+        .filter(|item| item.lateness <= 0)
+        .map(|item| item.profit)
+        .sum();
+
+    Ok(JobSequence {
+        items,
+        rejected,
+        max_lateness,
+        total_profit,
+    })
+}
+
+pub struct JobSequencingCommand;
+
+pub static COMMAND: JobSequencingCommand = JobSequencingCommand;
+
+inventory::submit!(Registration(&COMMAND));
+
+impl Command for JobSequencingCommand {
+    fn name(&self) -> &'static str {
+        "job-sequencing"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sequencing jobs with release times, processing times and deadlines"
+    }
+
+    fn problem(&self) -> &'static str {
+        "Given jobs with a release time, a processing time and a deadline, find an order to run \
+         them on a single machine."
+    }
+
+    fn modes(&self) -> Vec<Mode> {
+        vec![
+            Mode {
+                name: "edf",
+                summary: "Earliest-Deadline-First: whenever the machine is free, runs the \
+                          released job of the n with the earliest deadline",
+                time: "O(n log n)",
+                space: "O(n)",
+            },
+            Mode {
+                name: "profit",
+                summary: "Keeps the most profitable of the n unit jobs as long as the kept ones \
+                          can still all run on time",
+                time: "O(n^2 log n)",
+                space: "O(n)",
+            },
+        ]
+    }
+
+    fn example(&self) -> Example {
+        Example {
+            args: &["--mode", "edf"],
+            output: r#"{ "items": [
+    { "title": "job1", "start": 0, "end": 2, "deadline": 4, "lateness": -2, "profit": 10 },
+    { "title": "job2", "start": 2, "end": 3, "deadline": 2, "lateness": 1, "profit": 20 }
+], "rejected": [], "max_lateness": 1, "total_profit": 10 }"#,
+        }
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        vec![
+            json_file_arg(),
+            input_format_arg(),
+            Arg::new("mode")
+                .short('m')
+                .long("mode")
+                .value_name("MODE")
+                .default_value("edf")
+                .value_parser(JobSequencingMode::NAMES)
+                .help("algorithm mode: either \"edf\" or \"profit\""),
+        ]
+    }
+
+    fn input_example(&self) -> Option<&'static str> {
+        Some(
+            r#"{ "items": [
+    { "title": "job1", "release": 0, "processing_time": 2, "deadline": 4, "profit": 10 },
+    { "title": "job2", "release": 1, "processing_time": 1, "deadline": 2, "profit": 20 }
+] }"#,
+        )
+    }
+
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        Some(schema_for::<JobSchedule>("job-sequencing input"))
+    }
+
+    fn typed_input(
+        &self,
+        path: &PathBuf,
+        format: Option<InputFormat>,
+        context: &CommandContext,
+    ) -> Option<Result<serde_json::Value, Error>> {
+        Some(read_as::<JobSchedule>(path, format, context))
+    }
+
+    fn output_schema(&self) -> serde_json::Value {
+        schema_for::<JobSequence>("job-sequencing result")
+    }
+
+    fn generator(&self) -> Option<Generator> {
+        Some(Generator {
+            about: "Jobs with release times, processing times and deadlines, as consumed by `job-sequencing`",
+            args: vec![
+                generators::length_arg("number of jobs"),
+                Arg::new("unit")
+                    .short('u')
+                    .long("unit")
+                    .action(ArgAction::SetTrue)
+                    .help("only generate jobs with a processing time of 1, as needed by the \"profit\" mode"),
+            ],
+            generate: |matches, rng| {
+                let length = *matches.get_one::<usize>("length").unwrap();
+                let unit = matches.get_flag("unit");
+                generators::pretty(&generators::jobs(rng, length, unit))
+            },
+        })
+    }
+
+    fn bench(&self) -> Option<Bench> {
+        Some(Bench {
+            // profit mode runs EDF over the accepted jobs for every job, so it grows faster than
+            // quadratically
+            sizes: &[250, 500, 1_000],
+            worst_case: false,
+            generate: |mode, size, _, rng| {
+                // profit mode only takes unit jobs
+                let schedule: JobSchedule =
+                    serde_json::from_value(generators::jobs(rng, size, mode == "profit")).unwrap();
+                Box::new(move |mode| {
+                    timed(|| sequence_jobs(&schedule, mode, &CommandContext::default()))
+                })
+            },
+        })
+    }
+
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        context: &CommandContext,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let json_file_path = json_file_path(matches).unwrap();
+        let mode = matches.get_one::<String>("mode").unwrap();
+        debug!(
+            "Sequencing jobs from {} using {:?} method",
+            json_file_path.display(),
+            mode
+        );
+        let schedule = context.parse_input(json_file_path, input_format(matches))?;
+        let result = sequence_jobs(&schedule, mode, context)?;
+        Ok(serde_json::to_value(result)?)
+    }
+
+    fn report(&self, result: &serde_json::Value) -> Result<(), Box<dyn std::error::Error>> {
+        println!("{}", serde_json::to_string_pretty(result)?);
+        Ok(())
+    }
+}
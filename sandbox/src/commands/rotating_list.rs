@@ -1,27 +1,57 @@
 /*
  * Given the head of a linked list, rotate the list to the right by k places.
  */
-use log::debug;
+use clap::{value_parser, Arg, ArgMatches};
+use log::{debug, info};
+use std::{
+    error::Error,
+    io::{self, ErrorKind},
+};
+
+use crate::commands::{
+    timed, Bench, Command, CommandContext, Example, Generator, Mode, Registration,
+};
+use crate::generators;
+use crate::instrument::{self, Operation};
+use crate::schema::schema_for;
+use crate::trace::{TraceEvent, Tracer};
 
 pub enum RotatingDirection {
     Right,
 }
 
 impl RotatingDirection {
-    fn from_str(s: &str) -> Self {
+    pub const NAMES: [&'static str; 1] = ["right"];
+
+    fn from_str(s: &str) -> Result<Self, io::Error> {
         match s {
-            "right" => RotatingDirection::Right,
-            _ => panic!("Invalid mode: {}", s),
+            "right" => Ok(RotatingDirection::Right),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid mode: {}", s),
+            )),
         }
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Debug)]
 pub struct ListNode {
     pub val: i32,
     pub next: Option<Box<ListNode>>,
 }
 
+// by hand to count the copies, cloning a node clones the rest of the list along with it
+impl Clone for ListNode {
+    fn clone(&self) -> Self {
+        instrument::count(Operation::Clone, 1);
+        instrument::count(Operation::Allocation, 1);
+        ListNode {
+            val: self.val,
+            next: self.next.clone(),
+        }
+    }
+}
+
 impl ListNode {
     #[inline]
     fn new(val: i32) -> Self {
@@ -31,6 +61,7 @@ impl ListNode {
     fn from_vec(vec: Vec<i32>) -> Option<Box<ListNode>> {
         let mut current = None;
         for &val in vec.iter().rev() {
+            instrument::count(Operation::Allocation, 1);
             let mut node = ListNode::new(val);
             node.next = current;
             current = Some(Box::new(node));
@@ -42,6 +73,7 @@ impl ListNode {
         let mut current = head;
         let mut vec = Vec::new();
         while let Some(node) = current {
+            instrument::count(Operation::NodeHop, 1);
             vec.push(node.val);
             current = node.next;
         }
@@ -55,6 +87,7 @@ impl ListNode {
         let mut split = Some(self);
         // traverse to the n - 1 node and get the next node (which is the nth node)
         for _ in 0..n - 1 {
+            instrument::count(Operation::NodeHop, 1);
             // get the next node if available. if not return none
             if let Some(node) = split.map(|v| v.next.as_deref_mut()) {
                 split = node;
@@ -74,9 +107,19 @@ impl ListNode {
         }
     }
 
+    fn last(&self) -> &ListNode {
+        let mut tail = self;
+        while let Some(next) = tail.next.as_deref() {
+            instrument::count(Operation::NodeHop, 1);
+            tail = next;
+        }
+        tail
+    }
+
     fn extend_from_list(&mut self, new_tail: Box<ListNode>) {
         let mut tail = self;
         while tail.next.is_some() {
+            instrument::count(Operation::NodeHop, 1);
             tail = tail.next.as_deref_mut().unwrap();
         }
 
@@ -84,18 +127,23 @@ impl ListNode {
     }
 }
 
-fn rotate_right(head: Option<Box<ListNode>>, k: i32) -> Option<Box<ListNode>> {
+fn rotate_right(head: Option<Box<ListNode>>, k: i32, tracer: Tracer) -> Option<Box<ListNode>> {
     let mut pointer = head.clone();
     let mut count: usize = 0;
     while let Some(mut node) = pointer {
-        count = count + 1;
-        if node.next == None {
+        instrument::count(Operation::NodeHop, 1);
+        count += 1;
+        if node.next.is_none() {
             node.next = head;
             pointer = node.next.take();
             break;
         }
         pointer = node.next.take();
     }
+    if count == 0 {
+        // nothing to rotate in an empty list
+        return None;
+    }
     let rotating_index = count - (k as usize % count);
     debug!("rotating_index: {}", rotating_index);
     if rotating_index == count {
@@ -103,9 +151,14 @@ fn rotate_right(head: Option<Box<ListNode>>, k: i32) -> Option<Box<ListNode>> {
         return pointer.clone();
     }
     let mut new_tail = pointer.clone();
+    tracer.emit(|| TraceEvent::Split { at: rotating_index });
     let new_head = new_tail.as_mut().unwrap().split_at(rotating_index);
     match new_head {
         Some(mut head_pointer) => {
+            tracer.emit(|| TraceEvent::Relink {
+                from: head_pointer.last().val,
+                to: new_tail.as_ref().unwrap().val,
+            });
             head_pointer.extend_from_list(new_tail.clone().unwrap());
             Some(Box::new(head_pointer))
         }
@@ -113,21 +166,138 @@ fn rotate_right(head: Option<Box<ListNode>>, k: i32) -> Option<Box<ListNode>> {
     }
 }
 
-fn rotating_list_right(array: Vec<i32>, k: i32) -> Result<Vec<i32>, String> {
+fn rotating_list_right(array: Vec<i32>, k: i32, tracer: Tracer) -> Vec<i32> {
+    tracer.emit(|| TraceEvent::ListRotation {
+        numbers: array.clone(),
+        k,
+    });
     let head = ListNode::from_vec(array.clone());
 
-    let rotated_head = rotate_right(head, k);
-    let rotated_array = ListNode::to_vec(rotated_head);
-
-    Ok(rotated_array)
+    let rotated_head = rotate_right(head, k, tracer);
+    ListNode::to_vec(rotated_head)
 }
 
+/// The rotated list, sending the split and relinking of the nodes to the tracer
 pub fn rotating_list(
     array: Vec<i32>,
     k: i32,
-    rotating_direction: &String,
-) -> Result<Vec<i32>, String> {
-    match RotatingDirection::from_str(rotating_direction) {
-        RotatingDirection::Right => rotating_list_right(array, k),
+    rotating_direction: &str,
+    context: &CommandContext,
+) -> Result<Vec<i32>, io::Error> {
+    match RotatingDirection::from_str(rotating_direction)? {
+        RotatingDirection::Right => Ok(rotating_list_right(array, k, context.tracer)),
+    }
+}
+
+pub struct RotatingListCommand;
+
+pub static COMMAND: RotatingListCommand = RotatingListCommand;
+
+inventory::submit!(Registration(&COMMAND));
+
+impl Command for RotatingListCommand {
+    fn name(&self) -> &'static str {
+        "rotating-list"
+    }
+
+    fn description(&self) -> &'static str {
+        "Rotating a list by k rotations"
+    }
+
+    fn problem(&self) -> &'static str {
+        "Given the head of a linked list, rotate the list to the right by k places."
+    }
+
+    fn modes(&self) -> Vec<Mode> {
+        vec![Mode {
+            name: "right",
+            summary: "Closes the n nodes into a ring, then splits it k places before the end",
+            time: "O(n)",
+            space: "O(n)",
+        }]
+    }
+
+    fn example(&self) -> Example {
+        Example {
+            args: &["--numbers", "1,2,3,4,5", "--k", "2"],
+            output: "[4, 5, 1, 2, 3]",
+        }
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        vec![
+            Arg::new("numbers")
+                .short('n')
+                .long("numbers")
+                .value_name("NUMBERS")
+                .required(true)
+                .value_delimiter(',')
+                .value_parser(value_parser!(i32))
+                .help("numbers containing the list separated by commas")
+                .long_help("numbers containing the list separated by commas\n33, 74, 75, 71, 69, 72, 76, 73"),
+            Arg::new("k")
+                .short('k')
+                .long("k")
+                .value_name("K")
+                .required(true)
+                .value_parser(value_parser!(i32))
+                .help("number of rotations"),
+            Arg::new("rotation_direction")
+                .short('r')
+                .long("rotation-direction")
+                .value_name("ROTATION_DIRECTION")
+                .default_value("right")
+                .value_parser(RotatingDirection::NAMES)
+                .help("rotation direction: only \"right\" for now"),
+        ]
+    }
+
+    fn output_schema(&self) -> serde_json::Value {
+        schema_for::<Vec<i32>>("rotating-list result")
+    }
+
+    fn generator(&self) -> Option<Generator> {
+        Some(Generator {
+            about: "Numbers separated by commas, as passed to `rotating-list --numbers`",
+            args: vec![generators::length_arg("number of numbers")],
+            generate: |matches, rng| {
+                let length = *matches.get_one::<usize>("length").unwrap();
+                Ok(generators::join_numbers(&generators::numbers(rng, length)))
+            },
+        })
+    }
+
+    fn bench(&self) -> Option<Bench> {
+        Some(Bench {
+            sizes: &[1_000, 10_000],
+            worst_case: false,
+            generate: |_, size, _, rng| {
+                let numbers = generators::numbers(rng, size);
+                Box::new(move |mode| {
+                    // the list is consumed, so it is copied before the timing starts
+                    let numbers = numbers.clone();
+                    let k = numbers.len() as i32 / 3;
+                    timed(|| rotating_list(numbers, k, mode, &CommandContext::default()))
+                })
+            },
+        })
+    }
+
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        context: &CommandContext,
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let numbers: Vec<i32> = matches
+            .get_many::<i32>("numbers")
+            .unwrap()
+            .copied()
+            .collect();
+        let k = *matches.get_one::<i32>("k").unwrap();
+        let rotation_direction = matches.get_one::<String>("rotation_direction").unwrap();
+        // Implement the rotating list algorithm here
+        info!("Rotating list from {:?} by {:?} rotations", numbers, k);
+        let result = rotating_list(numbers, k, rotation_direction, context)?;
+        Ok(serde_json::to_value(result)?)
     }
 }
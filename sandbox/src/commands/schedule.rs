@@ -1,18 +1,31 @@
+use clap::{Arg, ArgMatches};
 use log::debug;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::{error::Error, io, path::PathBuf};
 
-use crate::utils::parse_json_file;
-use std::{io::Error, path::PathBuf}; // path buffer, to construct paths
+use crate::cancellation::Cancelled;
+use crate::commands::{
+    input_format, input_format_arg, json_file_arg, json_file_path, or_rendered_chart, read_as,
+    timed, Bench, Command, CommandContext, Example, Generator, Mode, Registration,
+};
+use crate::gantt::GanttChart;
+use crate::generators;
+use crate::input::InputFormat;
+use crate::instrument::{self, Operation};
+use crate::schema::schema_for;
+use crate::trace::{TraceEvent, TracedTask};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct ScheduleItem {
-    start: i32,
-    end: i32,
-    title: String,
+    pub(crate) start: i32,
+    pub(crate) end: i32,
+    pub(crate) title: String,
 }
 
 impl Clone for ScheduleItem {
     fn clone(&self) -> Self {
+        instrument::count(Operation::Clone, 1);
         ScheduleItem {
             start: self.start,
             end: self.end,
@@ -21,22 +34,42 @@ impl Clone for ScheduleItem {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Schedule {
-    items: Vec<ScheduleItem>,
+    pub(crate) items: Vec<ScheduleItem>,
 }
 
-pub fn get_optimal_schedule(path: &PathBuf) -> Result<(), Error> {
-    let schedule = parse_json_file::<Schedule>(path).unwrap();
+impl ScheduleItem {
+    fn traced(&self) -> TracedTask {
+        TracedTask {
+            title: self.title.clone(),
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+/// The most tasks that do not overlap, sending the picked and rejected tasks to the tracer
+pub fn get_optimal_schedule(
+    schedule: &Schedule,
+    context: &CommandContext,
+) -> Result<Schedule, Cancelled> {
+    let tracer = context.tracer;
     debug!("Extracted schedule: {:?}", schedule);
+    tracer.emit(|| TraceEvent::ScheduleTasks {
+        tasks: schedule.items.iter().map(ScheduleItem::traced).collect(),
+    });
     let mut optimal_schedule_items: Vec<ScheduleItem> = Vec::new();
     let mut schedule_items = schedule.items.clone();
+    instrument::count(Operation::Allocation, 2);
 
-    while schedule_items.len() > 0 {
+    while !schedule_items.is_empty() {
+        context.cancellation.check()?;
         let mut earliest_end = i32::MAX;
         let mut earliest_index = 0;
 
         for (i, item) in schedule_items.iter().enumerate() {
+            instrument::count(Operation::Comparison, 1);
             if item.end < earliest_end {
                 earliest_end = item.end;
                 earliest_index = i;
@@ -44,20 +77,174 @@ pub fn get_optimal_schedule(path: &PathBuf) -> Result<(), Error> {
         }
         optimal_schedule_items.push(schedule_items.remove(earliest_index));
         let picked_item = optimal_schedule_items.last().unwrap();
+        tracer.emit(|| TraceEvent::Pick {
+            task: picked_item.traced(),
+        });
 
         schedule_items = schedule_items
             .iter()
-            .filter(|item| item.start >= picked_item.end)
+            .filter(|item| {
+                instrument::count(Operation::Comparison, 1);
+                let fits = item.start >= picked_item.end;
+                if !fits {
+                    tracer.emit(|| TraceEvent::Reject {
+                        task: item.traced(),
+                        conflicts_with: picked_item.title.clone(),
+                    });
+                }
+                fits
+            })
             .cloned()
             .collect();
+        instrument::count(Operation::Allocation, 1);
     }
 
-    let optimal_schedule = Schedule {
+    Ok(Schedule {
         items: optimal_schedule_items,
-    };
+    })
+}
+
+pub struct ScheduleCommand;
+
+pub static COMMAND: ScheduleCommand = ScheduleCommand;
+
+inventory::submit!(Registration(&COMMAND));
+
+impl Command for ScheduleCommand {
+    fn name(&self) -> &'static str {
+        "schedule"
+    }
+
+    fn description(&self) -> &'static str {
+        "Optimal Scheduling of picking tasks with overlapping intervals"
+    }
+
+    fn problem(&self) -> &'static str {
+        "Given tasks with a start and an end time, pick the largest set of tasks that do not \
+         overlap, a task ending when the next one starts."
+    }
+
+    fn modes(&self) -> Vec<Mode> {
+        vec![Mode {
+            name: "greedy",
+            summary: "Picks the task of the n that ends first, drops the ones overlapping it and \
+                      repeats with the rest",
+            time: "O(n^2)",
+            space: "O(n)",
+        }]
+    }
+
+    fn example(&self) -> Example {
+        Example {
+            args: &[],
+            output: r#"{ "items": [{ "start": 1, "end": 3, "title": "Task 1" }] }"#,
+        }
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        vec![
+            json_file_arg(),
+            input_format_arg(),
+            Arg::new("render")
+                .long("render")
+                .value_name("FORMAT")
+                .value_parser(["ascii", "svg"])
+                .help("draw a Gantt chart of the tasks instead of listing the picked ones: either \"ascii\" or \"svg\""),
+        ]
+    }
 
-    let result = serde_json::to_string_pretty(&optimal_schedule)?;
+    fn input_example(&self) -> Option<&'static str> {
+        Some(
+            r#"{ "items": [
+    { "start": 1, "end": 3, "title": "Task 1" },
+    { "start": 2, "end": 4, "title": "Task 2" }
+] }"#,
+        )
+    }
+
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        Some(schema_for::<Schedule>("schedule input"))
+    }
 
-    println!("{}", result);
-    Ok(())
+    fn typed_input(
+        &self,
+        path: &PathBuf,
+        format: Option<InputFormat>,
+        context: &CommandContext,
+    ) -> Option<Result<serde_json::Value, io::Error>> {
+        Some(read_as::<Schedule>(path, format, context))
+    }
+
+    fn output_schema(&self) -> serde_json::Value {
+        or_rendered_chart(schema_for::<Schedule>("schedule result"))
+    }
+
+    fn generator(&self) -> Option<Generator> {
+        Some(Generator {
+            about: "Tasks with start and end times, as consumed by `schedule`",
+            args: vec![
+                generators::length_arg("number of tasks"),
+                Arg::new("overlap")
+                    .short('o')
+                    .long("overlap")
+                    .value_name("OVERLAP")
+                    .value_parser(generators::parse_ratio)
+                    .default_value("0.5")
+                    .help("chance (0.0 to 1.0) that a task starts before the previous one ended"),
+            ],
+            generate: |matches, rng| {
+                let length = *matches.get_one::<usize>("length").unwrap();
+                let overlap = *matches.get_one::<f64>("overlap").unwrap();
+                generators::pretty(&generators::schedule(rng, length, overlap))
+            },
+        })
+    }
+
+    fn bench(&self) -> Option<Bench> {
+        Some(Bench {
+            sizes: &[1_000, 10_000],
+            worst_case: false,
+            generate: |_, size, _, rng| {
+                let schedule: Schedule =
+                    serde_json::from_value(generators::schedule(rng, size, 0.5)).unwrap();
+                Box::new(move |_| {
+                    timed(|| get_optimal_schedule(&schedule, &CommandContext::default()))
+                        .map_err(io::Error::from)
+                })
+            },
+        })
+    }
+
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        context: &CommandContext,
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let json_file_path = json_file_path(matches).unwrap();
+        // Implement the scheduling algorithm here
+        debug!("Scheduling tasks from {}", json_file_path.display());
+        let schedule = context.parse_input(json_file_path, input_format(matches))?;
+        // the chart is the result, so the server and the RPC session can draw one too
+        match matches.get_one::<String>("render").map(String::as_str) {
+            Some("ascii") => {
+                let chart = GanttChart::selection(&schedule, &context.cancellation)?;
+                return Ok(chart.to_ascii().into());
+            }
+            Some("svg") => {
+                let chart = GanttChart::selection(&schedule, &context.cancellation)?;
+                return Ok(chart.to_svg().into());
+            }
+            _ => {}
+        }
+        let result = get_optimal_schedule(&schedule, context)?;
+        Ok(serde_json::to_value(result)?)
+    }
+
+    fn report(&self, result: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+        match result {
+            serde_json::Value::String(chart) => print!("{}", chart),
+            _ => println!("{}", serde_json::to_string_pretty(result)?),
+        }
+        Ok(())
+    }
 }
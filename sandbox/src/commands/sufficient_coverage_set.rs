@@ -1,59 +1,159 @@
-use std::{collections::HashSet, io::Error, path::PathBuf};
+use clap::{value_parser, Arg, ArgMatches};
+use std::{
+    collections::HashSet,
+    error::Error,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
 
-use crate::utils::parse_json_file;
+use crate::cancellation::{CancellationToken, Cancelled};
+use crate::commands::{
+    input_format, input_format_arg, json_file_arg, json_file_path, or_rendered_chart, read_as,
+    timed, Bench, Command, CommandContext, Example, Generator, Mode, Registration,
+};
+use crate::coverage_matrix::CoverageChart;
+use crate::generators;
+use crate::input::InputFormat;
+use crate::instrument::{self, Operation};
+use crate::progress::binomial;
+use crate::schema::schema_for;
+use crate::trace::TraceEvent;
+use crate::utils;
 use log::{debug, info};
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct TargetCoverage {
-    numbers: Vec<u32>,
-    min_numbers_to_cover: u32,
+    pub(crate) numbers: Vec<u32>,
+    pub(crate) min_numbers_to_cover: u32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct ItemSet {
-    tickets: Vec<Vec<u32>>,
-    target_coverage: TargetCoverage,
+    pub(crate) tickets: Vec<Vec<u32>>,
+    pub(crate) target_coverage: TargetCoverage,
     expected: bool,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct SufficientCoverageProblemSet {
-    items: Vec<ItemSet>,
+    pub(crate) items: Vec<ItemSet>,
 }
 
-fn get_combinations(numbers: &Vec<u32>, k: usize) -> Vec<HashSet<u32>> {
+impl SufficientCoverageProblemSet {
+    /// Checks that every problem covers combinations of at least one and at most all its numbers
+    pub(crate) fn validate(&self) -> Result<(), io::Error> {
+        for (i, item) in self.items.iter().enumerate() {
+            let target = &item.target_coverage;
+            if target.min_numbers_to_cover < 1
+                || target.min_numbers_to_cover as usize > target.numbers.len()
+            {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Problem {} has a min_numbers_to_cover of {}, it must be between 1 and its {} numbers",
+                        i,
+                        target.min_numbers_to_cover,
+                        target.numbers.len()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn get_combinations(
+    numbers: &[u32],
+    k: usize,
+    cancellation: &CancellationToken,
+) -> Result<Vec<HashSet<u32>>, Cancelled> {
+    cancellation.check()?;
     if k > numbers.len() {
-        return vec![];
+        return Ok(vec![]);
+    }
+    // the one combination of nothing
+    if k == 0 {
+        instrument::count(Operation::Allocation, 1);
+        return Ok(vec![HashSet::new()]);
     }
     if k == 1 {
-        return numbers
+        instrument::count(Operation::Allocation, numbers.len() as u64);
+        return Ok(numbers
             .iter()
             .map(|&num| HashSet::from([num]))
-            .collect::<Vec<HashSet<u32>>>();
+            .collect::<Vec<HashSet<u32>>>());
     }
 
     if k == numbers.len() {
-        return vec![HashSet::from_iter(numbers.iter().cloned())];
+        instrument::count(Operation::Allocation, 1);
+        return Ok(vec![HashSet::from_iter(numbers.iter().cloned())]);
     }
 
     let mut result = Vec::new();
 
     let first = numbers[0];
-    let rest = &numbers[1..].to_vec();
+    let rest = &numbers[1..];
 
     // # Combinations that include the first element
-    for combo in get_combinations(rest, k - 1) {
+    for combo in get_combinations(rest, k - 1, cancellation)? {
+        instrument::count(Operation::Allocation, 1);
         let mut new_combo = HashSet::new();
         new_combo.extend(combo);
         new_combo.insert(first);
         result.push(new_combo);
     }
     // # Combinations that exclude the first element
-    let without_first = get_combinations(rest, k);
+    let without_first = get_combinations(rest, k, cancellation)?;
     result.extend(without_first);
 
-    result
+    Ok(result)
+}
+
+/**
+ * Checks if a ticket that is not a superset of the combo still covers it, as it covers enough of
+ * the combo along with numbers outside of it.
+ */
+pub(crate) fn is_implied_cover(
+    ticket: &HashSet<u32>,
+    combo: &HashSet<u32>,
+    numbers: &[u32],
+    min_numbers_to_cover: usize,
+    cancellation: &CancellationToken,
+) -> Result<bool, Cancelled> {
+    // get all the value that is matched in both the ticket and combo
+    // we know this is less than min_numbers_to_cover since combo is not a subset of ticket
+    instrument::count(Operation::Allocation, 1);
+    let intersected_value: HashSet<u32> = HashSet::from_iter(ticket.intersection(combo).cloned());
+    debug!(
+        "Intersected value: {:?}, ticket: {:?}",
+        intersected_value, ticket
+    );
+    if intersected_value.is_empty() {
+        return Ok(false);
+    }
+    instrument::count(Operation::Allocation, 1);
+    let remaining_numbers: Vec<u32> = HashSet::from_iter(numbers.iter().cloned())
+        .difference(combo)
+        .copied()
+        .collect();
+    debug!(
+        "Remaining numbers: {:?}, ticket: {:?}",
+        remaining_numbers, ticket
+    );
+
+    // get all combinations from the remaining numbers in combo with length equal to min_numbers_to_cover - intersected_value.len()
+    let remaining_combinations = get_combinations(
+        &remaining_numbers,
+        min_numbers_to_cover - intersected_value.len(),
+        cancellation,
+    )?;
+    Ok(remaining_combinations.iter().any(|combo| {
+        instrument::count(Operation::SubsetCheck, 1);
+        ticket.is_superset(combo)
+    }))
 }
 
 /**
@@ -63,7 +163,19 @@ fn get_combinations(numbers: &Vec<u32>, k: usize) -> Vec<HashSet<u32>> {
  *  numbers in ticket is unique
  *  numbers in target coverage is unique
  */
-fn does_ticket_cover_target(item_set: &ItemSet) -> bool {
+fn does_ticket_cover_target(
+    problem: usize,
+    item_set: &ItemSet,
+    context: &CommandContext,
+) -> Result<bool, Cancelled> {
+    let (cancellation, tracer, progress) =
+        (&context.cancellation, context.tracer, context.progress);
+    tracer.emit(|| TraceEvent::CoverageProblem {
+        problem,
+        numbers: item_set.target_coverage.numbers.clone(),
+        tickets: item_set.tickets.clone(),
+        min_numbers_to_cover: item_set.target_coverage.min_numbers_to_cover,
+    });
     // convert each ticket to a set as we just need to check existence of each number
     let tickets = item_set
         .tickets
@@ -71,83 +183,318 @@ fn does_ticket_cover_target(item_set: &ItemSet) -> bool {
         .map(|ticket| HashSet::from_iter(ticket.iter().cloned()))
         .collect::<Vec<HashSet<u32>>>();
     let numbers = item_set.target_coverage.numbers.clone();
+    instrument::count(Operation::Allocation, tickets.len() as u64 + 1);
+    instrument::count(Operation::Clone, 1);
     debug!("tickets: {:?}", tickets);
     // generate all permutation of numbers in target coverage with length equal to min_numbers_to_cover
     let combinations = get_combinations(
         &numbers,
         item_set.target_coverage.min_numbers_to_cover as usize,
-    );
+        cancellation,
+    )?;
     debug!("winning combinations: {:?}", combinations);
 
     let mut combo_covered_count = 0;
+    let combo_check = |combo: &HashSet<u32>, ticket: Option<usize>, implied: bool| {
+        let mut combo: Vec<u32> = combo.iter().copied().collect();
+        combo.sort_unstable();
+        TraceEvent::ComboCheck {
+            problem,
+            combo,
+            ticket,
+            implied,
+        }
+    };
 
     // check if any permutation is covered by any ticket
     for combo in combinations.iter() {
+        cancellation.check()?;
         // if it is a direct subset of any ticket then it is covered
-        if tickets.iter().any(|ticket| ticket.is_superset(&combo)) {
+        if let Some(i) = tickets.iter().position(|ticket| {
+            instrument::count(Operation::SubsetCheck, 1);
+            ticket.is_superset(combo)
+        }) {
+            tracer.emit(|| combo_check(combo, Some(i), false));
             combo_covered_count += 1;
+            if let Some(progress) = progress {
+                progress.advance();
+            }
             continue;
         }
 
-        let is_implied_covered = tickets.iter().any(|ticket| {
-            // get all the value that is matched in both the ticket and combo
-            // we know this is less than min_numbers_to_cover since combo is not a subset of ticket
-            let intersected_value: HashSet<u32> =
-                HashSet::from_iter(ticket.intersection(&combo).cloned());
-            debug!(
-                "Intersected value: {:?}, ticket: {:?}",
-                intersected_value, ticket
-            );
-            if intersected_value.is_empty() {
-                return false;
-            }
-            let remaining_numbers = HashSet::from_iter(numbers.iter().cloned())
-                .difference(&combo)
-                .map(|x| x.clone())
-                .collect();
-            debug!(
-                "Remaining numbers: {:?}, ticket: {:?}",
-                remaining_numbers, ticket
-            );
-
-            // get all combinations from the remaining numbers in combo with length equal to min_numbers_to_cover - intersected_value.len()
-            let remaining_combinations = get_combinations(
-                &remaining_numbers,
-                item_set.target_coverage.min_numbers_to_cover as usize - intersected_value.len(),
-            );
-            if remaining_combinations
-                .iter()
-                .any(|combo| ticket.is_superset(&combo))
-            {
-                return true;
+        let mut is_implied_covered = false;
+        for (i, ticket) in tickets.iter().enumerate() {
+            if is_implied_cover(
+                ticket,
+                combo,
+                &numbers,
+                item_set.target_coverage.min_numbers_to_cover as usize,
+                cancellation,
+            )? {
+                tracer.emit(|| combo_check(combo, Some(i), true));
+                is_implied_covered = true;
+                break;
             }
-            false
-        });
+        }
 
         if is_implied_covered {
             combo_covered_count += 1;
+            if let Some(progress) = progress {
+                progress.advance();
+            }
             continue;
         }
+        if let Some(progress) = progress {
+            progress.advance();
+        }
         info!("Uncovered combo: {:?}", combo);
-        return false;
+        tracer.emit(|| combo_check(combo, None, false));
+        return Ok(false);
     }
     debug!(
         "combo covered count: {}, combination length: {:?}",
         combo_covered_count,
         combinations.len()
     );
-    return combo_covered_count == combinations.len();
+    Ok(combo_covered_count == combinations.len())
 }
 
-pub fn evaluate_sufficient_coverage(path: &PathBuf) -> Result<(), Error> {
-    let test_set = parse_json_file::<SufficientCoverageProblemSet>(path).unwrap();
-    for problem in test_set.items {
+/**
+ * Evaluates every problem in the set, returning whether each one is covered. Stops early once the
+ * token of the context is cancelled, sends the checked combinations to its tracer and counts them
+ * in its progress, with a stage per problem.
+ */
+pub fn evaluate_sufficient_coverage(
+    test_set: &SufficientCoverageProblemSet,
+    context: &CommandContext,
+) -> Result<Vec<bool>, Cancelled> {
+    let (tracer, progress) = (context.tracer, context.progress);
+    if let Some(progress) = progress {
+        for (i, problem) in test_set.items.iter().enumerate() {
+            let target = &problem.target_coverage;
+            progress.add_stage(
+                format!("problem {}", i),
+                "combos",
+                binomial(
+                    target.numbers.len() as u64,
+                    target.min_numbers_to_cover as u64,
+                ),
+            );
+        }
+    }
+    let mut results = Vec::with_capacity(test_set.items.len());
+    for (i, problem) in test_set.items.iter().enumerate() {
         info!("Evaluating Problem: {:?}", problem);
-        let result = does_ticket_cover_target(&problem);
+        if let Some(progress) = progress {
+            progress.start(i);
+        }
+        let result = does_ticket_cover_target(i, problem, context)?;
+        if let Some(progress) = progress {
+            progress.finish(if result { "covered" } else { "not covered" });
+        }
+        tracer.emit(|| TraceEvent::ProblemResult {
+            problem: i,
+            covered: result,
+        });
         info!(
             "Actual Result: {}, Expected Result: {}",
             result, problem.expected
         );
+        results.push(result);
+    }
+    Ok(results)
+}
+
+pub struct SufficientCoverageSetCommand;
+
+pub static COMMAND: SufficientCoverageSetCommand = SufficientCoverageSetCommand;
+
+inventory::submit!(Registration(&COMMAND));
+
+impl Command for SufficientCoverageSetCommand {
+    fn name(&self) -> &'static str {
+        "sufficient-coverage-set"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sufficient Coverage Set Problem"
+    }
+
+    fn problem(&self) -> &'static str {
+        "Given tickets of numbers and a target of numbers, tell whether every combination of \
+         min_numbers_to_cover of the target numbers is covered by a ticket, either directly or \
+         by implication."
+    }
+
+    fn modes(&self) -> Vec<Mode> {
+        vec![Mode {
+            name: "greedy",
+            summary: "Checks every combination of k of the n numbers against each of the t \
+                      tickets, stopping at the first one that covers it",
+            time: "O(t * C(n, k)^2 * k)",
+            space: "O(C(n, k) * k)",
+        }]
+    }
+
+    fn example(&self) -> Example {
+        Example {
+            args: &[],
+            output: "[true, false]",
+        }
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        vec![
+            json_file_arg(),
+            input_format_arg(),
+            Arg::new("render")
+                .long("render")
+                .value_name("FORMAT")
+                .value_parser(["ascii", "svg"])
+                .help("draw the combination by ticket coverage matrix of each problem instead of evaluating them: either \"ascii\" or \"svg\""),
+        ]
+    }
+
+    fn input_example(&self) -> Option<&'static str> {
+        Some(
+            r#"{ "items": [
+    { "tickets": [[1, 2], [2, 3], [3, 4]], "target_coverage": { "numbers": [1, 2, 3, 4], "min_numbers_to_cover": 2 }, "expected": true },
+    { "tickets": [[1, 2], [2, 3], [3, 4]], "target_coverage": { "numbers": [1, 2, 3, 4], "min_numbers_to_cover": 3 }, "expected": false }
+] }"#,
+        )
+    }
+
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        Some(schema_for::<SufficientCoverageProblemSet>(
+            "sufficient-coverage-set input",
+        ))
+    }
+
+    fn typed_input(
+        &self,
+        path: &PathBuf,
+        format: Option<InputFormat>,
+        context: &CommandContext,
+    ) -> Option<Result<serde_json::Value, io::Error>> {
+        Some(read_as::<SufficientCoverageProblemSet>(
+            path, format, context,
+        ))
+    }
+
+    fn output_schema(&self) -> serde_json::Value {
+        or_rendered_chart(schema_for::<Vec<bool>>("sufficient-coverage-set result"))
+    }
+
+    fn generator(&self) -> Option<Generator> {
+        let count = |id: &'static str, long: &'static str, default: &'static str| {
+            Arg::new(id)
+                .long(long)
+                .value_name(id.to_uppercase())
+                .value_parser(value_parser!(usize))
+                .default_value(default)
+        };
+        Some(Generator {
+            about: "Problems with tickets and a target coverage, as consumed by `sufficient-coverage-set`",
+            args: vec![
+                count("problems", "problems", "2")
+                    .short('p')
+                    .help("number of problems"),
+                count("pool_size", "pool-size", "5").help("size of the pool of numbers to cover"),
+                count("tickets", "tickets", "3")
+                    .short('t')
+                    .help("number of tickets per problem"),
+                count("ticket_size", "ticket-size", "3")
+                    .help("number of distinct numbers on each ticket"),
+                count("min_numbers_to_cover", "min-numbers-to-cover", "2")
+                    .short('m')
+                    .help("numbers each combination has to cover"),
+            ],
+            generate: |matches, rng| {
+                let count = |id: &str| *matches.get_one::<usize>(id).unwrap();
+                let mut value = generators::coverage(
+                    rng,
+                    count("problems"),
+                    count("pool_size"),
+                    count("tickets"),
+                    count("ticket_size"),
+                    count("min_numbers_to_cover"),
+                );
+                // fill in the expected results, so the file can be used as a regression input
+                let test_set: SufficientCoverageProblemSet = serde_json::from_value(value.clone())?;
+                test_set.validate()?;
+                let results = utils::quietly(|| {
+                    evaluate_sufficient_coverage(&test_set, &CommandContext::default())
+                })
+                .unwrap();
+                for (item, result) in value["items"]
+                    .as_array_mut()
+                    .unwrap()
+                    .iter_mut()
+                    .zip(results)
+                {
+                    item["expected"] = json!(result);
+                }
+                generators::pretty(&value)
+            },
+        })
+    }
+
+    fn bench(&self) -> Option<Bench> {
+        Some(Bench {
+            // the size is the pool of numbers, the tickets and combinations grow quickly with it
+            sizes: &[20, 40, 80],
+            worst_case: false,
+            generate: |_, size, _, rng| {
+                let test_set: SufficientCoverageProblemSet = serde_json::from_value(
+                    generators::coverage(rng, 1, size, size, 6.min(size), 2),
+                )
+                .unwrap();
+                Box::new(move |_| {
+                    // never cancelled
+                    timed(|| evaluate_sufficient_coverage(&test_set, &CommandContext::default()))
+                        .map_err(io::Error::other)
+                })
+            },
+        })
+    }
+
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        context: &CommandContext,
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let json_file_path = json_file_path(matches).unwrap();
+        // Implement the sufficient coverage set algorithm here
+        debug!(
+            "Finding sufficient coverage set from {}",
+            json_file_path.display()
+        );
+        let test_set: SufficientCoverageProblemSet =
+            context.parse_input(json_file_path, input_format(matches))?;
+        test_set.validate()?;
+        // the chart is the result, so the server and the RPC session can draw one too
+        match matches.get_one::<String>("render").map(String::as_str) {
+            Some("ascii") => {
+                return Ok(CoverageChart::new(&test_set, &context.cancellation)?
+                    .to_ascii()
+                    .into())
+            }
+            Some("svg") => {
+                return Ok(CoverageChart::new(&test_set, &context.cancellation)?
+                    .to_svg()
+                    .into())
+            }
+            _ => {}
+        }
+        let result = evaluate_sufficient_coverage(&test_set, context)?;
+        Ok(serde_json::to_value(result)?)
+    }
+
+    fn report(&self, result: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+        match result {
+            serde_json::Value::String(chart) => print!("{}", chart),
+            _ => println!("{}", result),
+        }
+        Ok(())
     }
-    Ok(())
 }
@@ -0,0 +1,256 @@
+/*
+ * Layered defaults for the command arguments, from lowest to highest precedence:
+ *
+ *  1. the built-in defaults of the arguments
+ *  2. the user config in `$XDG_CONFIG_HOME/dat_cli/config.toml` (or `~/.config/dat_cli/config.toml`)
+ *  3. the project config in `./dat_cli.toml`
+ *  4. environment variables in the form of `DAT_CLI_<COMMAND>_<ARGUMENT>`,
+ *     e.g. `DAT_CLI_DAILY_TEMPERATURE_MODE=Stack`
+ *
+ * Arguments given on the command line always win. The config files are in the form of
+ *
+ * [daily-temperature]
+ * mode = "Stack"
+ *
+ * [rotating-list]
+ * rotation_direction = "right"
+ */
+use std::{
+    fmt, fs,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
+
+use crate::{registry, utils};
+
+pub const PROJECT_CONFIG_FILE: &str = "dat_cli.toml";
+const ENV_PREFIX: &str = "DAT_CLI_";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "built-in default"),
+            ConfigSource::File(path) => write!(f, "{}", path.display()),
+            ConfigSource::Env(name) => write!(f, "environment variable {}", name),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Setting {
+    pub command: String,
+    pub arg: String,
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+#[derive(Default)]
+pub struct Config {
+    /// effective settings, one per command and argument
+    settings: Vec<Setting>,
+    /// config files that were found, in the order they were applied
+    files: Vec<PathBuf>,
+    /// problems that did not stop the config from loading, to log once logging is up
+    warnings: Vec<String>,
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    utils::xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("dat_cli").join("config.toml"))
+}
+
+/// Environment variable for an argument, e.g. `DAT_CLI_ROTATING_LIST_ROTATION_DIRECTION`
+pub fn env_var_name(command: &str, arg: &str) -> String {
+    format!("{}{}_{}", ENV_PREFIX, command, arg)
+        .to_uppercase()
+        .replace('-', "_")
+}
+
+/// Config values as they would be typed on the command line, lists separated by commas
+fn value_to_arg(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(value_to_arg)
+            .collect::<Option<Vec<String>>>()
+            .map(|values| values.join(",")),
+        _ => None,
+    }
+}
+
+impl Config {
+    /// Loads the config from the files and environment variables
+    pub fn load() -> Result<Self, Error> {
+        let mut config = Config::default();
+        for command in registry() {
+            for arg in command.args() {
+                if let Some(value) = arg.get_default_values().first() {
+                    config.set(
+                        command.name(),
+                        arg.get_id().as_str(),
+                        value.to_string_lossy().to_string(),
+                        ConfigSource::Default,
+                    );
+                }
+            }
+        }
+
+        let project_config_path = std::env::current_dir()
+            .ok()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE));
+        for path in [user_config_path(), project_config_path]
+            .into_iter()
+            .flatten()
+        {
+            if path.is_file() {
+                config.load_file(path)?;
+            }
+        }
+
+        for command in registry() {
+            for arg in command.args() {
+                let name = env_var_name(command.name(), arg.get_id().as_str());
+                if let Ok(value) = std::env::var(&name) {
+                    config.set(
+                        command.name(),
+                        arg.get_id().as_str(),
+                        value,
+                        ConfigSource::Env(name),
+                    );
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    fn load_file(&mut self, path: PathBuf) -> Result<(), Error> {
+        let content = fs::read_to_string(&path)?;
+        let table: toml::Table = toml::from_str(&content).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid config {}: {}", path.display(), e),
+            )
+        })?;
+
+        for (command_name, args) in table.iter() {
+            let Some(command) = registry()
+                .into_iter()
+                .find(|command| command.name() == command_name)
+            else {
+                self.warnings.push(format!(
+                    "Unknown command {} in {}",
+                    command_name,
+                    path.display()
+                ));
+                continue;
+            };
+            let Some(args) = args.as_table() else {
+                self.warnings.push(format!(
+                    "Expected a table for {} in {}",
+                    command_name,
+                    path.display()
+                ));
+                continue;
+            };
+            let known_args = command.args();
+            for (arg_name, value) in args.iter() {
+                // accept both the argument id and its long name
+                let arg_id = arg_name.replace('-', "_");
+                if !known_args.iter().any(|arg| arg.get_id() == arg_id.as_str()) {
+                    self.warnings.push(format!(
+                        "Unknown argument {}.{} in {}",
+                        command_name,
+                        arg_name,
+                        path.display()
+                    ));
+                    continue;
+                }
+                match value_to_arg(value) {
+                    Some(value) => self.set(
+                        command_name,
+                        &arg_id,
+                        value,
+                        ConfigSource::File(path.clone()),
+                    ),
+                    None => self.warnings.push(format!(
+                        "Unsupported value for {}.{} in {}",
+                        command_name,
+                        arg_name,
+                        path.display()
+                    )),
+                }
+            }
+        }
+        self.files.push(path);
+        Ok(())
+    }
+
+    fn set(&mut self, command: &str, arg: &str, value: String, source: ConfigSource) {
+        let setting = Setting {
+            command: command.to_string(),
+            arg: arg.to_string(),
+            value,
+            source,
+        };
+        match self
+            .settings
+            .iter_mut()
+            .find(|s| s.command == command && s.arg == arg)
+        {
+            Some(existing) => *existing = setting,
+            None => self.settings.push(setting),
+        }
+    }
+
+    pub fn get(&self, command: &str, arg: &str) -> Option<&Setting> {
+        self.settings
+            .iter()
+            .find(|s| s.command == command && s.arg == arg)
+    }
+
+    pub fn settings(&self) -> &[Setting] {
+        &self.settings
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Uses the configured values as the defaults of the subcommand's arguments
+    pub fn apply(&self, command: clap::Command) -> clap::Command {
+        let name = command.get_name().to_string();
+        command.mut_args(|arg| match self.get(&name, arg.get_id().as_str()) {
+            Some(setting) if setting.source != ConfigSource::Default => {
+                arg.default_value(setting.value.clone()).required(false)
+            }
+            _ => arg,
+        })
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for setting in self.settings.iter() {
+            writeln!(
+                f,
+                "{}.{} = {:?} ({})",
+                setting.command, setting.arg, setting.value, setting.source
+            )?;
+        }
+        Ok(())
+    }
+}
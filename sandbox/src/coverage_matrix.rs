@@ -0,0 +1,303 @@
+/*
+ * Coverage matrices of sufficient-coverage problems, as a terminal grid or as an SVG heatmap.
+ *
+ * A matrix has one row per combination of `min_numbers_to_cover` numbers and one column per
+ * ticket, each cell telling whether the ticket covers the combination directly, by implication or
+ * not at all. A row without any covering ticket is a hole in the ticket set.
+ */
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::cancellation::{CancellationToken, Cancelled};
+use crate::commands::sufficient_coverage_set::{
+    get_combinations, is_implied_cover, ItemSet, SufficientCoverageProblemSet,
+};
+
+const SVG_CELL: f64 = 16.0;
+const SVG_CHAR_WIDTH: f64 = 7.0;
+const SVG_TITLE_HEIGHT: f64 = 24.0;
+const SVG_GAP: f64 = 24.0;
+
+/// Ordered from the worst to the best coverage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CoverageCell {
+    Missed,
+    Implied,
+    Direct,
+}
+
+impl CoverageCell {
+    fn symbol(self) -> char {
+        match self {
+            CoverageCell::Direct => '#',
+            CoverageCell::Implied => '+',
+            CoverageCell::Missed => '.',
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            CoverageCell::Direct => "#2e7d32",
+            CoverageCell::Implied => "#a5d6a7",
+            CoverageCell::Missed => "#eeeeee",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            CoverageCell::Direct => "covered directly",
+            CoverageCell::Implied => "covered by implication",
+            CoverageCell::Missed => "not covered",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CoverageMatrix {
+    pub problem: usize,
+    pub tickets: Vec<Vec<u32>>,
+    /// the combinations, sorted, one per row
+    pub combos: Vec<Vec<u32>>,
+    /// `cells[row][ticket]`
+    pub cells: Vec<Vec<CoverageCell>>,
+}
+
+impl CoverageMatrix {
+    fn new(
+        problem: usize,
+        item_set: &ItemSet,
+        cancellation: &CancellationToken,
+    ) -> Result<Self, Cancelled> {
+        let numbers = &item_set.target_coverage.numbers;
+        let k = item_set.target_coverage.min_numbers_to_cover as usize;
+        let tickets: Vec<HashSet<u32>> = item_set
+            .tickets
+            .iter()
+            .map(|ticket| ticket.iter().copied().collect())
+            .collect();
+        let mut combos = get_combinations(numbers, k, cancellation)?;
+        combos.sort_by_cached_key(sorted);
+
+        let mut cells = Vec::with_capacity(combos.len());
+        for combo in combos.iter() {
+            let mut row = Vec::with_capacity(tickets.len());
+            for ticket in tickets.iter() {
+                row.push(if ticket.is_superset(combo) {
+                    CoverageCell::Direct
+                } else if is_implied_cover(ticket, combo, numbers, k, cancellation)? {
+                    CoverageCell::Implied
+                } else {
+                    CoverageCell::Missed
+                });
+            }
+            cells.push(row);
+        }
+        Ok(CoverageMatrix {
+            problem,
+            tickets: item_set.tickets.clone(),
+            combos: combos.iter().map(sorted).collect(),
+            cells,
+        })
+    }
+
+    /// How the combination of a row is covered, by the best of the tickets
+    pub fn row_coverage(&self, row: usize) -> CoverageCell {
+        self.cells[row]
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(CoverageCell::Missed)
+    }
+
+    /// Number of combinations no ticket covers
+    pub fn holes(&self) -> usize {
+        (0..self.combos.len())
+            .filter(|&row| self.row_coverage(row) == CoverageCell::Missed)
+            .count()
+    }
+
+    fn combo_label(&self, row: usize) -> String {
+        join(&self.combos[row])
+    }
+}
+
+fn sorted(combo: &HashSet<u32>) -> Vec<u32> {
+    let mut combo: Vec<u32> = combo.iter().copied().collect();
+    combo.sort_unstable();
+    combo
+}
+
+fn join(numbers: &[u32]) -> String {
+    numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// The coverage matrices of every problem in a set
+#[derive(Debug, Clone, Default)]
+pub struct CoverageChart {
+    pub matrices: Vec<CoverageMatrix>,
+}
+
+impl CoverageChart {
+    /// Checks every combination against every ticket, stopping early once the token is cancelled
+    pub fn new(
+        test_set: &SufficientCoverageProblemSet,
+        cancellation: &CancellationToken,
+    ) -> Result<Self, Cancelled> {
+        let matrices = test_set
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item_set)| CoverageMatrix::new(i, item_set, cancellation))
+            .collect::<Result<_, _>>()?;
+        Ok(CoverageChart { matrices })
+    }
+
+    /// The matrices as grids of `#` (direct), `+` (implied) and `.` (not covered)
+    pub fn to_ascii(&self) -> String {
+        let mut chart = String::new();
+        for matrix in self.matrices.iter() {
+            let label_width = (0..matrix.combos.len())
+                .map(|row| matrix.combo_label(row).len())
+                .max()
+                .unwrap_or(0);
+            let _ = writeln!(
+                chart,
+                "problem {}: {} combinations, {} not covered",
+                matrix.problem,
+                matrix.combos.len(),
+                matrix.holes()
+            );
+            for (i, ticket) in matrix.tickets.iter().enumerate() {
+                let _ = writeln!(chart, "  t{}: {}", i, join(ticket));
+            }
+            // ticket numbers, one column each, in a line per digit for the wide sets
+            let digits = matrix.tickets.len().saturating_sub(1).to_string().len();
+            for digit in 0..digits {
+                let header: String = (0..matrix.tickets.len())
+                    .map(|i| {
+                        let number = format!("{:>digits$}", i);
+                        number.chars().nth(digit).unwrap()
+                    })
+                    .collect();
+                let _ = writeln!(chart, "  {:>label_width$} {}", "", header);
+            }
+            for (row, cells) in matrix.cells.iter().enumerate() {
+                let grid: String = cells.iter().map(|cell| cell.symbol()).collect();
+                let _ = write!(
+                    chart,
+                    "  {:>label_width$} {}",
+                    matrix.combo_label(row),
+                    grid
+                );
+                if matrix.row_coverage(row) == CoverageCell::Missed {
+                    chart.push_str("  <- not covered");
+                }
+                chart.push('\n');
+            }
+            chart.push('\n');
+        }
+        chart.push_str("# covered directly, + covered by implication, . not covered\n");
+        chart
+    }
+
+    /// The matrices as a heatmap in a standalone SVG document, one below the other
+    pub fn to_svg(&self) -> String {
+        let mut body = String::new();
+        let mut y = 0.0;
+        let mut width: f64 = 0.0;
+        for matrix in self.matrices.iter() {
+            let label_width = (0..matrix.combos.len())
+                .map(|row| matrix.combo_label(row).len())
+                .max()
+                .unwrap_or(0) as f64
+                * SVG_CHAR_WIDTH
+                + 8.0;
+            let _ = writeln!(
+                body,
+                r#"  <text x="0" y="{}" font-weight="bold">problem {}: {} combinations, {} not covered</text>"#,
+                y + 14.0,
+                matrix.problem,
+                matrix.combos.len(),
+                matrix.holes()
+            );
+            y += SVG_TITLE_HEIGHT;
+            for (i, ticket) in matrix.tickets.iter().enumerate() {
+                let x = label_width + i as f64 * SVG_CELL;
+                let _ = writeln!(
+                    body,
+                    r#"  <text x="{}" y="{}" text-anchor="middle" font-size="9"><title>{}</title>t{}</text>"#,
+                    x + SVG_CELL / 2.0,
+                    y + 12.0,
+                    join(ticket),
+                    i
+                );
+            }
+            y += SVG_CELL;
+            for (row, cells) in matrix.cells.iter().enumerate() {
+                let fill = match matrix.row_coverage(row) {
+                    CoverageCell::Missed => "#c62828",
+                    _ => "black",
+                };
+                let _ = writeln!(
+                    body,
+                    r#"  <text x="{}" y="{}" text-anchor="end" fill="{}">{}</text>"#,
+                    label_width - 6.0,
+                    y + 12.0,
+                    fill,
+                    matrix.combo_label(row)
+                );
+                for (i, cell) in cells.iter().enumerate() {
+                    let _ = writeln!(
+                        body,
+                        r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="white"><title>{} by t{}: {}</title></rect>"#,
+                        label_width + i as f64 * SVG_CELL,
+                        y,
+                        SVG_CELL,
+                        SVG_CELL,
+                        cell.color(),
+                        matrix.combo_label(row),
+                        i,
+                        cell.description()
+                    );
+                }
+                y += SVG_CELL;
+            }
+            width = width.max(label_width + matrix.tickets.len() as f64 * SVG_CELL);
+            y += SVG_GAP;
+        }
+
+        // the legend
+        for (i, cell) in [
+            CoverageCell::Direct,
+            CoverageCell::Implied,
+            CoverageCell::Missed,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let x = i as f64 * 170.0;
+            let _ = writeln!(
+                body,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+                x,
+                y,
+                SVG_CELL,
+                SVG_CELL,
+                cell.color(),
+                x + SVG_CELL + 4.0,
+                y + 12.0,
+                cell.description()
+            );
+        }
+        width = width.max(3.0 * 170.0);
+        let height = y + SVG_CELL;
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\" font-size=\"12\">\n{body}</svg>\n"
+        )
+    }
+}
@@ -0,0 +1,295 @@
+/*
+ * C API of the algorithms, declared in `include/dat_cli.h`.
+ *
+ * Every function takes its input as a pointer and a length, writes its results into buffers
+ * owned by the caller and returns a status. On failure the error message is stored in `*error`
+ * (when `error` is not NULL), which the caller frees with `dat_cli_free_error`.
+ *
+ * `dat_cli_run` runs any registered command from its command line and returns its result as JSON,
+ * the other functions are typed shortcuts to single algorithms.
+ */
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::commands::daily_temperature::get_daily_temperature;
+use crate::commands::rotating_list::rotating_list;
+use crate::commands::schedule::{get_optimal_schedule, Schedule, ScheduleItem};
+use crate::{execute_command, parse_command, CommandContext};
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum DatCliStatus {
+    Ok = 0,
+    /// a pointer that must not be NULL was NULL
+    NullPointer = 1,
+    InvalidArgument = 2,
+    /// the algorithm failed unexpectedly, which is a bug
+    Internal = 3,
+}
+
+/// Algorithm for `dat_cli_daily_temperature`, which takes it as a plain integer so any value
+/// coming from C is safe to pass
+#[repr(C)]
+pub enum DatCliDailyTemperatureMode {
+    Stack = 0,
+    Reverse = 1,
+}
+
+/// A task to schedule, running from `start` until `end`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct DatCliTask {
+    pub start: i32,
+    pub end: i32,
+}
+
+struct FfiError {
+    status: DatCliStatus,
+    message: String,
+}
+
+impl FfiError {
+    fn new(status: DatCliStatus, message: impl ToString) -> Self {
+        FfiError {
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// The input as a slice, NULL is only fine for an empty input
+unsafe fn input<'a, T>(data: *const T, len: usize, name: &str) -> Result<&'a [T], FfiError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(FfiError::new(
+            DatCliStatus::NullPointer,
+            format!("{} is NULL", name),
+        ));
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+/// The output buffer as a slice, NULL is only fine for an empty output
+unsafe fn output<'a, T>(data: *mut T, len: usize, name: &str) -> Result<&'a mut [T], FfiError> {
+    if len == 0 {
+        return Ok(&mut []);
+    }
+    if data.is_null() {
+        return Err(FfiError::new(
+            DatCliStatus::NullPointer,
+            format!("{} is NULL", name),
+        ));
+    }
+    Ok(slice::from_raw_parts_mut(data, len))
+}
+
+/// Runs `f`, turning its errors and panics into a status and an error message
+unsafe fn call(error: *mut *mut c_char, f: impl FnOnce() -> Result<(), FfiError>) -> DatCliStatus {
+    if !error.is_null() {
+        *error = ptr::null_mut();
+    }
+    // unwinding into C is undefined behavior
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        Err(FfiError::new(
+            DatCliStatus::Internal,
+            "the algorithm failed unexpectedly",
+        ))
+    });
+    match result {
+        Ok(()) => DatCliStatus::Ok,
+        Err(e) => {
+            if !error.is_null() {
+                // messages are built here and never contain NUL bytes
+                *error = CString::new(e.message).unwrap_or_default().into_raw();
+            }
+            e.status
+        }
+    }
+}
+
+/// Number of days to wait for a warmer temperature after each day, 0 if there is none. `mode` is
+/// one of `DatCliDailyTemperatureMode`. `days` must have room for `len` values.
+///
+/// # Safety
+/// `temperatures` must point to `len` values and `days` to room for `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn dat_cli_daily_temperature(
+    temperatures: *const i32,
+    len: usize,
+    mode: u32,
+    days: *mut usize,
+    error: *mut *mut c_char,
+) -> DatCliStatus {
+    call(error, || {
+        let temperatures = input(temperatures, len, "temperatures")?;
+        let days = output(days, len, "days")?;
+        const STACK: u32 = DatCliDailyTemperatureMode::Stack as u32;
+        const REVERSE: u32 = DatCliDailyTemperatureMode::Reverse as u32;
+        let mode = match mode {
+            STACK => "Stack",
+            REVERSE => "Reverse",
+            _ => {
+                return Err(FfiError::new(
+                    DatCliStatus::InvalidArgument,
+                    format!("unknown daily temperature mode {}", mode),
+                ))
+            }
+        };
+        let result = get_daily_temperature(temperatures, mode, &CommandContext::default())
+            .map_err(|e| FfiError::new(DatCliStatus::InvalidArgument, e))?;
+        days.copy_from_slice(&result);
+        Ok(())
+    })
+}
+
+/// Rotates the numbers to the right by `k` places. `rotated` must have room for `len` values.
+///
+/// # Safety
+/// `numbers` must point to `len` values and `rotated` to room for `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn dat_cli_rotate_right(
+    numbers: *const i32,
+    len: usize,
+    k: i32,
+    rotated: *mut i32,
+    error: *mut *mut c_char,
+) -> DatCliStatus {
+    call(error, || {
+        let numbers = input(numbers, len, "numbers")?;
+        let rotated = output(rotated, len, "rotated")?;
+        if k < 0 {
+            return Err(FfiError::new(
+                DatCliStatus::InvalidArgument,
+                format!("k must not be negative, got {}", k),
+            ));
+        }
+        let result = rotating_list(numbers.to_vec(), k, "right", &CommandContext::default())
+            .map_err(|e| FfiError::new(DatCliStatus::InvalidArgument, e))?;
+        rotated.copy_from_slice(&result);
+        Ok(())
+    })
+}
+
+/// Picks the most tasks that do not overlap. The indices of the picked tasks are written to
+/// `picked` in the order they run, which must have room for `len` indices, and their number to
+/// `picked_len`.
+///
+/// # Safety
+/// `tasks` must point to `len` tasks, `picked` to room for `len` indices and `picked_len` to a
+/// single value.
+#[no_mangle]
+pub unsafe extern "C" fn dat_cli_schedule(
+    tasks: *const DatCliTask,
+    len: usize,
+    picked: *mut usize,
+    picked_len: *mut usize,
+    error: *mut *mut c_char,
+) -> DatCliStatus {
+    call(error, || {
+        let tasks = input(tasks, len, "tasks")?;
+        let picked = output(picked, len, "picked")?;
+        let picked_len = output(picked_len, 1, "picked_len")?;
+        if let Some((i, task)) = tasks
+            .iter()
+            .enumerate()
+            .find(|(_, task)| task.end < task.start)
+        {
+            return Err(FfiError::new(
+                DatCliStatus::InvalidArgument,
+                format!(
+                    "task {} ends at {} before it starts at {}",
+                    i, task.end, task.start
+                ),
+            ));
+        }
+
+        // the index is the title, to find the picked tasks again
+        let schedule = Schedule {
+            items: tasks
+                .iter()
+                .enumerate()
+                .map(|(i, task)| ScheduleItem {
+                    start: task.start,
+                    end: task.end,
+                    title: i.to_string(),
+                })
+                .collect(),
+        };
+        // nothing cancels the default context
+        let result = get_optimal_schedule(&schedule, &CommandContext::default())
+            .map_err(|e| FfiError::new(DatCliStatus::Internal, e))?;
+        for (slot, item) in picked.iter_mut().zip(result.items.iter()) {
+            *slot = item.title.parse().unwrap();
+        }
+        picked_len[0] = result.items.len();
+        Ok(())
+    })
+}
+
+/// Runs a command from its arguments, e.g. `{"rotating-list", "-n", "1,2,3", "-k", "1"}`, as on
+/// the command line without the program name. Its result is written to `*result` as JSON, which the
+/// caller frees with `dat_cli_free_result`. File based commands read the file given with
+/// `--json-file-path`.
+///
+/// # Safety
+/// `args` must point to `len` NUL terminated strings and `result` to a single pointer.
+#[no_mangle]
+pub unsafe extern "C" fn dat_cli_run(
+    args: *const *const c_char,
+    len: usize,
+    result: *mut *mut c_char,
+    error: *mut *mut c_char,
+) -> DatCliStatus {
+    call(error, || {
+        let args = input(args, len, "args")?;
+        let result = output(result, 1, "result")?;
+        let args = args
+            .iter()
+            .enumerate()
+            .map(|(i, &arg)| match arg.is_null() {
+                true => Err(FfiError::new(
+                    DatCliStatus::NullPointer,
+                    format!("args[{}] is NULL", i),
+                )),
+                false => Ok(CStr::from_ptr(arg).to_string_lossy().into_owned()),
+            })
+            .collect::<Result<Vec<String>, FfiError>>()?;
+        let invocation = parse_command(args)
+            .map_err(|e| FfiError::new(DatCliStatus::InvalidArgument, e.render()))?;
+        let context = CommandContext::default();
+        let value = execute_command(&invocation, &context)
+            .map_err(|e| FfiError::new(DatCliStatus::InvalidArgument, e))?;
+        // JSON escapes NUL bytes, so there are none to fail on
+        result[0] = CString::new(value.to_string())
+            .unwrap_or_default()
+            .into_raw();
+        Ok(())
+    })
+}
+
+/// Frees a result returned by `dat_cli_run`. NULL is ignored.
+///
+/// # Safety
+/// `result` must be NULL or a result returned by this library, which was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn dat_cli_free_result(result: *mut c_char) {
+    if !result.is_null() {
+        drop(CString::from_raw(result));
+    }
+}
+
+/// Frees an error message returned by any of the functions. NULL is ignored.
+///
+/// # Safety
+/// `error` must be NULL or a message returned by this library, which was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn dat_cli_free_error(error: *mut c_char) {
+    if !error.is_null() {
+        drop(CString::from_raw(error));
+    }
+}
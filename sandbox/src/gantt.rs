@@ -0,0 +1,258 @@
+/*
+ * Gantt charts of schedules, as text for the terminal or as SVG.
+ *
+ * A chart is a list of rows on a shared timeline. The chart of a selection has one row per input
+ * task, showing what was picked and what blocked the rest.
+ *
+ * There is no chart with one row per lane: every command here picks or orders tasks on a single
+ * timeline, none partitions them into lanes yet. Such a command would chart its lanes as rows of
+ * selected bars.
+ */
+use std::fmt::Write;
+
+use crate::cancellation::{CancellationToken, Cancelled};
+use crate::commands::schedule::{get_optimal_schedule, Schedule};
+use crate::commands::CommandContext;
+use crate::trace::{TraceEvent, TraceRecorder, TracedTask, Tracer};
+
+/// Columns of the timeline in the text chart
+const ASCII_WIDTH: usize = 60;
+/// Width in pixels of the timeline in the SVG chart, next to the labels and notes
+const SVG_WIDTH: f64 = 600.0;
+const SVG_LABEL_WIDTH: f64 = 120.0;
+const SVG_NOTE_WIDTH: f64 = 200.0;
+const SVG_ROW_HEIGHT: f64 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarStatus {
+    Selected,
+    Rejected,
+}
+
+#[derive(Debug, Clone)]
+pub struct GanttBar {
+    pub title: String,
+    pub start: i32,
+    pub end: i32,
+    pub status: BarStatus,
+}
+
+#[derive(Debug, Clone)]
+pub struct GanttRow {
+    pub label: String,
+    pub bars: Vec<GanttBar>,
+    /// shown after the row, like the task that blocked a rejected one
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GanttChart {
+    pub rows: Vec<GanttRow>,
+}
+
+impl GanttChart {
+    /**
+     * Runs the scheduling algorithm over the tasks and charts every one of them, the picked ones
+     * highlighted and the rejected ones annotated with the picked task that overlaps them. Stops
+     * with `Cancelled` once the token is cancelled.
+     */
+    pub fn selection(
+        schedule: &Schedule,
+        cancellation: &CancellationToken,
+    ) -> Result<Self, Cancelled> {
+        let recorder = TraceRecorder::default();
+        get_optimal_schedule(
+            schedule,
+            &CommandContext {
+                cancellation: cancellation.clone(),
+                tracer: Tracer::new(&recorder),
+                ..CommandContext::default()
+            },
+        )?;
+
+        let tasks: Vec<TracedTask> = schedule
+            .items
+            .iter()
+            .map(|item| TracedTask {
+                title: item.title.clone(),
+                start: item.start,
+                end: item.end,
+            })
+            .collect();
+        let mut outcomes: Vec<Option<(BarStatus, Option<String>)>> = vec![None; tasks.len()];
+        for event in recorder.into_trace("schedule").events {
+            let (task, outcome) = match event {
+                TraceEvent::Pick { task } => (task, (BarStatus::Selected, None)),
+                TraceEvent::Reject {
+                    task,
+                    conflicts_with,
+                } => (
+                    task,
+                    (
+                        BarStatus::Rejected,
+                        Some(format!("blocked by {}", conflicts_with)),
+                    ),
+                ),
+                _ => continue,
+            };
+            // tasks may share a title and times, the first one without an outcome is the one
+            if let Some(i) = (0..tasks.len()).find(|&i| outcomes[i].is_none() && tasks[i] == task) {
+                outcomes[i] = Some(outcome);
+            }
+        }
+
+        let rows = tasks
+            .into_iter()
+            .zip(outcomes)
+            .map(|(task, outcome)| {
+                // every task is either picked or rejected, rejected is the safe fallback
+                let (status, note) = outcome.unwrap_or((BarStatus::Rejected, None));
+                GanttRow {
+                    label: task.title.clone(),
+                    bars: vec![GanttBar {
+                        title: task.title,
+                        start: task.start,
+                        end: task.end,
+                        status,
+                    }],
+                    note,
+                }
+            })
+            .collect();
+        Ok(GanttChart { rows })
+    }
+
+    /// First and last time on the timeline, None for a chart without bars
+    fn span(&self) -> Option<(i32, i32)> {
+        let bars = self.rows.iter().flat_map(|row| row.bars.iter());
+        let start = bars.clone().map(|bar| bar.start).min()?;
+        let end = bars.map(|bar| bar.end).max()?;
+        Some((start, end.max(start)))
+    }
+
+    /// The chart as text, `#` for the selected tasks and `-` for the rejected ones
+    pub fn to_ascii(&self) -> String {
+        let Some((start, end)) = self.span() else {
+            return "(no tasks)\n".to_string();
+        };
+        let length = (end as f64 - start as f64).max(1.0);
+        let column = |time: i32| {
+            ((time as f64 - start as f64) / length * ASCII_WIDTH as f64).round() as usize
+        };
+        let label_width = self
+            .rows
+            .iter()
+            .map(|row| row.label.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut chart = String::new();
+        for row in self.rows.iter() {
+            let mut line = vec![' '; ASCII_WIDTH + 1];
+            for bar in row.bars.iter() {
+                let from = column(bar.start);
+                // even an empty task takes one column
+                let to = column(bar.end).max(from + 1).min(line.len());
+                let mark = match bar.status {
+                    BarStatus::Selected => '#',
+                    BarStatus::Rejected => '-',
+                };
+                line[from..to].fill(mark);
+            }
+            let line: String = line.into_iter().collect();
+            let _ = write!(chart, "{:<label_width$} |{}|", row.label, line);
+            if let Some(note) = &row.note {
+                let _ = write!(chart, " {}", note);
+            }
+            chart.push('\n');
+        }
+        let axis_start = start.to_string();
+        let _ = writeln!(
+            chart,
+            "{:<label_width$}  {}{:>width$}",
+            "",
+            axis_start,
+            end,
+            width = ASCII_WIDTH + 1 - axis_start.len()
+        );
+        chart
+    }
+
+    /// The chart as a standalone SVG document
+    pub fn to_svg(&self) -> String {
+        let (start, end) = self.span().unwrap_or((0, 0));
+        let scale = SVG_WIDTH / (end as f64 - start as f64).max(1.0);
+        let x = |time: i32| SVG_LABEL_WIDTH + (time as f64 - start as f64) * scale;
+        let width = SVG_LABEL_WIDTH + SVG_WIDTH + SVG_NOTE_WIDTH;
+        let height = (self.rows.len() as f64 + 1.5) * SVG_ROW_HEIGHT;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="12">"#
+        );
+        for (i, row) in self.rows.iter().enumerate() {
+            let y = i as f64 * SVG_ROW_HEIGHT;
+            let _ = writeln!(
+                svg,
+                r#"  <text x="4" y="{}">{}</text>"#,
+                y + 16.0,
+                escape(&row.label)
+            );
+            for bar in row.bars.iter() {
+                let (fill, opacity) = match bar.status {
+                    BarStatus::Selected => ("#2e7d32", 1.0),
+                    BarStatus::Rejected => ("#9e9e9e", 0.5),
+                };
+                let _ = writeln!(
+                    svg,
+                    r#"  <rect x="{:.1}" y="{}" width="{:.1}" height="{}" fill="{}" fill-opacity="{}"><title>{} [{}, {})</title></rect>"#,
+                    x(bar.start),
+                    y + 4.0,
+                    (x(bar.end) - x(bar.start)).max(1.0),
+                    SVG_ROW_HEIGHT - 8.0,
+                    fill,
+                    opacity,
+                    escape(&bar.title),
+                    bar.start,
+                    bar.end
+                );
+            }
+            if let Some(note) = &row.note {
+                let _ = writeln!(
+                    svg,
+                    r##"  <text x="{}" y="{}" fill="#616161">{}</text>"##,
+                    SVG_LABEL_WIDTH + SVG_WIDTH + 8.0,
+                    y + 16.0,
+                    escape(note)
+                );
+            }
+        }
+        let axis = self.rows.len() as f64 * SVG_ROW_HEIGHT + 4.0;
+        let _ = writeln!(
+            svg,
+            r#"  <line x1="{}" y1="{axis}" x2="{}" y2="{axis}" stroke="black"/>"#,
+            x(start),
+            x(end)
+        );
+        for (time, anchor) in [(start, "start"), (end, "end")] {
+            let _ = writeln!(
+                svg,
+                r#"  <text x="{:.1}" y="{}" text-anchor="{}">{}</text>"#,
+                x(time),
+                axis + 16.0,
+                anchor,
+                time
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,255 @@
+/*
+ * Random inputs for every command, in the same JSON shapes the commands consume.
+ *
+ * The generator is seeded, so the same seed always produces the same inputs. Each command declares
+ * the `gen` subcommand of its own in `Command::generator`, from the inputs generated here.
+ */
+use clap::{value_parser, Arg, ArgMatches, FromArgMatches, Subcommand};
+use serde_json::{json, Value};
+use std::io::Error;
+
+use crate::commands::{find_command, registry, Command};
+
+/// A command to generate an input for, along with the arguments of its `gen` subcommand
+pub struct GenInvocation {
+    command: &'static dyn Command,
+    matches: ArgMatches,
+}
+
+/// The `gen` subcommands, one per command with a generator
+fn gen_subcommands() -> Vec<clap::Command> {
+    registry()
+        .into_iter()
+        .filter_map(|command| {
+            let generator = command.generator()?;
+            Some(
+                clap::Command::new(command.name())
+                    .about(generator.about)
+                    .args(generator.args),
+            )
+        })
+        .collect()
+}
+
+impl FromArgMatches for GenInvocation {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let (name, matches) = matches
+            .subcommand()
+            .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::MissingSubcommand))?;
+        let command = find_command(name)
+            .filter(|command| command.generator().is_some())
+            .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::InvalidSubcommand))?;
+        Ok(GenInvocation {
+            command,
+            matches: matches.clone(),
+        })
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = GenInvocation::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+impl Subcommand for GenInvocation {
+    fn augment_subcommands(command: clap::Command) -> clap::Command {
+        command
+            .subcommands(gen_subcommands())
+            .subcommand_required(true)
+    }
+
+    fn augment_subcommands_for_update(command: clap::Command) -> clap::Command {
+        GenInvocation::augment_subcommands(command)
+    }
+
+    fn has_subcommand(name: &str) -> bool {
+        find_command(name).is_some_and(|command| command.generator().is_some())
+    }
+}
+
+/// The `--length` argument of the generators of list-like inputs
+pub fn length_arg(help: &'static str) -> Arg {
+    Arg::new("length")
+        .short('l')
+        .long("length")
+        .value_name("LENGTH")
+        .value_parser(value_parser!(usize))
+        .default_value("10")
+        .help(help)
+}
+
+/// Parses a ratio between 0.0 and 1.0
+pub fn parse_ratio(s: &str) -> Result<f64, String> {
+    let ratio = s
+        .parse::<f64>()
+        .map_err(|e| format!("Invalid ratio {}: {}", s, e))?;
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(format!("Invalid ratio {}: must be between 0.0 and 1.0", s));
+    }
+    Ok(ratio)
+}
+
+/// Numbers separated by commas, the input of the list based commands
+pub fn join_numbers(numbers: &[i32]) -> String {
+    numbers
+        .iter()
+        .map(|number| number.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Pretty JSON, the input of the file based commands
+pub fn pretty(value: &Value) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(value)?)
+}
+
+/// Generates the input of the command with its `gen` arguments
+pub fn run_gen(invocation: GenInvocation, seed: u64) -> Result<String, Error> {
+    let mut rng = Rng::new(seed);
+    // only commands with a generator are parsed into an invocation
+    let generator = invocation.command.generator().unwrap();
+    (generator.generate)(&invocation.matches, &mut rng)
+}
+
+/// SplitMix64, small and good enough for generating test inputs
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in `low..high`
+    pub fn range(&mut self, low: i32, high: i32) -> i32 {
+        let span = (high as i64 - low as i64).max(1) as u64;
+        (low as i64 + (self.next_u64() % span) as i64) as i32
+    }
+
+    /// Random number in `0.0..1.0`
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Picks `count` distinct values out of `values`, keeping their relative order
+    pub fn sample<T: Copy>(&mut self, values: &[T], count: usize) -> Vec<T> {
+        let mut picked = Vec::with_capacity(count);
+        let mut remaining = count.min(values.len());
+        for (i, &value) in values.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+            // selection sampling, every value is picked with probability remaining / left
+            if (self.next_u64() % (values.len() - i) as u64) < remaining as u64 {
+                picked.push(value);
+                remaining -= 1;
+            }
+        }
+        picked
+    }
+}
+
+/// Daily temperatures in the range of 30 to 100 degrees
+pub fn temperatures(rng: &mut Rng, length: usize) -> Vec<i32> {
+    (0..length).map(|_| rng.range(30, 101)).collect()
+}
+
+/**
+ * The worst case of the `Reverse` mode of daily-temperature: every day is colder than the one
+ * before up to the warmest last day, so every day looks ahead all the way to the end.
+ */
+pub fn falling_temperatures(length: usize) -> Vec<i32> {
+    let mut temperatures: Vec<i32> = (0..length).map(|i| 100 - i as i32).collect();
+    if let Some(last) = temperatures.last_mut() {
+        *last = 101;
+    }
+    temperatures
+}
+
+/// A list of numbers to rotate
+pub fn numbers(rng: &mut Rng, length: usize) -> Vec<i32> {
+    (0..length).map(|_| rng.range(-1000, 1001)).collect()
+}
+
+/**
+ * A schedule of `length` tasks. The overlap density (0.0 to 1.0) is the chance that a task starts
+ * before the previous one ended.
+ */
+pub fn schedule(rng: &mut Rng, length: usize, overlap: f64) -> Value {
+    let mut start = 0;
+    let mut items = Vec::with_capacity(length);
+    for i in 0..length {
+        let duration = rng.range(1, 20);
+        items.push(json!({
+            "start": start,
+            "end": start + duration,
+            "title": format!("task{}", i + 1),
+        }));
+        start = if rng.unit() < overlap {
+            start + rng.range(0, duration)
+        } else {
+            start + duration + rng.range(0, 5)
+        };
+    }
+    json!({ "items": items })
+}
+
+/// Jobs for job sequencing, unit jobs only if `unit` is set
+pub fn jobs(rng: &mut Rng, length: usize, unit: bool) -> Value {
+    let horizon = length as i32 * 2;
+    let items: Vec<Value> = (0..length)
+        .map(|i| {
+            let release = rng.range(0, horizon);
+            let processing_time = if unit { 1 } else { rng.range(1, 10) };
+            json!({
+                "title": format!("job{}", i + 1),
+                "release": release,
+                "processing_time": processing_time,
+                "deadline": release + processing_time + rng.range(0, horizon),
+                "profit": rng.range(1, 100),
+            })
+        })
+        .collect();
+    json!({ "items": items })
+}
+
+/**
+ * A sufficient coverage problem set. Each problem draws its tickets from a pool of `pool_size`
+ * numbers and has to cover every combination of `min_numbers_to_cover` of them.
+ */
+pub fn coverage(
+    rng: &mut Rng,
+    problems: usize,
+    pool_size: usize,
+    tickets: usize,
+    ticket_size: usize,
+    min_numbers_to_cover: usize,
+) -> Value {
+    let pool: Vec<u32> = (1..=pool_size as u32).collect();
+    let items: Vec<Value> = (0..problems)
+        .map(|_| {
+            let tickets: Vec<Vec<u32>> = (0..tickets)
+                .map(|_| rng.sample(&pool, ticket_size))
+                .collect();
+            json!({
+                "tickets": tickets,
+                "target_coverage": {
+                    "numbers": pool,
+                    "min_numbers_to_cover": min_numbers_to_cover,
+                },
+                // unknown until evaluated
+                "expected": false,
+            })
+        })
+        .collect();
+    json!({ "items": items })
+}
@@ -0,0 +1,231 @@
+/*
+ * Input files of the commands, in any of the supported formats. The format comes from `--format`
+ * or from the extension of the file, and `-` reads the input from stdin (as JSON unless
+ * `--format` says otherwise).
+ *
+ * A CSV file holds the rows of the `items` of the input, one column per field, which suits the
+ * tabular inputs like schedules and jobs. Empty cells are left out so the defaults apply, and a
+ * cell that reads as a number or a bool is one, unless the field it goes in takes a string.
+ *
+ * Every error names the file along with the line and column it happened at, a CSV error names
+ * the column by its header.
+ */
+use serde::de::{
+    self,
+    value::{self, MapDeserializer, SeqDeserializer},
+    IntoDeserializer, Visitor,
+};
+use std::{
+    fs,
+    io::{self, Error, ErrorKind, Read},
+    path::Path,
+};
+
+/// The path that reads the input from stdin
+pub const STDIN_PATH: &str = "-";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    Json,
+    Yaml,
+    Toml,
+    Csv,
+}
+
+impl InputFormat {
+    pub const NAMES: [&'static str; 4] = ["json", "yaml", "toml", "csv"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(InputFormat::Json),
+            "yaml" | "yml" => Some(InputFormat::Yaml),
+            "toml" => Some(InputFormat::Toml),
+            "csv" => Some(InputFormat::Csv),
+            _ => None,
+        }
+    }
+
+    /// The format a file is in going by its extension, JSON for anything unknown
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| Self::from_name(&extension.to_ascii_lowercase()))
+            .unwrap_or(InputFormat::Json)
+    }
+}
+
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+/// Reads and deserializes an input file, or stdin for `-`, in the given format or the one its
+/// extension implies
+pub fn parse_input_file<T: de::DeserializeOwned>(
+    path: &Path,
+    format: Option<InputFormat>,
+) -> Result<T, Error> {
+    let (source, content) = if is_stdin(path) {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        ("stdin".to_string(), content)
+    } else {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        (path.display().to_string(), content)
+    };
+    let format = format.unwrap_or_else(|| match is_stdin(path) {
+        true => InputFormat::Json,
+        false => InputFormat::from_path(path),
+    });
+    parse_input(&content, format)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", source, e)))
+}
+
+/// Deserializes the content of an input file, the errors carry the line and column
+pub fn parse_input<T: de::DeserializeOwned>(
+    content: &str,
+    format: InputFormat,
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match format {
+        InputFormat::Json => serde_json::from_str(content)?,
+        InputFormat::Yaml => serde_yaml::from_str(content)?,
+        // the message spans a few lines, pointing at the error in the line it quotes
+        InputFormat::Toml => toml::from_str(content)?,
+        InputFormat::Csv => {
+            let items = SeqDeserializer::new(csv_items(content)?.into_iter());
+            T::deserialize(MapDeserializer::new(std::iter::once(("items", items))))?
+        }
+    })
+}
+
+/// Reads the rows of a CSV file, to deserialize as `{ "items": [ { <header>: <cell>, ... }, ... ] }`
+fn csv_items(content: &str) -> Result<Vec<CsvRecord>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let header = reader.headers().map_err(csv_error)?.clone();
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(csv_error)?;
+            let cells = header
+                .iter()
+                .zip(record.iter())
+                .filter(|(_, cell)| !cell.is_empty())
+                .map(|(column, cell)| {
+                    let cell = CsvCell {
+                        column: column.to_string(),
+                        value: cell.to_string(),
+                    };
+                    (column.to_string(), cell)
+                })
+                .collect();
+            let line = record.position().map_or(0, |position| position.line());
+            Ok(CsvRecord { line, cells })
+        })
+        .collect()
+}
+
+fn csv_error(e: csv::Error) -> String {
+    match e.kind() {
+        csv::ErrorKind::UnequalLengths {
+            pos: Some(position),
+            expected_len,
+            len,
+        } => format!(
+            "expected {} cells as in the header, got {} at line {}",
+            expected_len,
+            len,
+            position.line()
+        ),
+        _ => e.to_string(),
+    }
+}
+
+/// A row as a map from the header to its cells, errors in it are reported for its line
+struct CsvRecord {
+    line: u64,
+    cells: Vec<(String, CsvCell)>,
+}
+
+impl<'de> IntoDeserializer<'de, value::Error> for CsvRecord {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for CsvRecord {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, value::Error> {
+        let line = self.line;
+        visitor
+            .visit_map(MapDeserializer::new(self.cells.into_iter()))
+            .map_err(|e: value::Error| de::Error::custom(format!("{} at line {}", e, line)))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+/// A cell, a number or a bool where the target takes one and text where it takes a string
+struct CsvCell {
+    column: String,
+    value: String,
+}
+
+impl<'de> IntoDeserializer<'de, value::Error> for CsvCell {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for CsvCell {
+    type Error = value::Error;
+
+    /// Guesses the type for untyped targets like `serde_json::Value`, typed targets take what
+    /// they expect from the guess. A guess can turn text like a title of `1` into a number, so
+    /// the input is read through `Command::typed_input` wherever its JSON form matters
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, value::Error> {
+        let result = if let Ok(value) = self.value.parse::<i64>() {
+            visitor.visit_i64(value)
+        } else if let Ok(value) = self.value.parse::<f64>() {
+            visitor.visit_f64(value)
+        } else if let Ok(value) = self.value.parse::<bool>() {
+            visitor.visit_bool(value)
+        } else {
+            visitor.visit_str(&self.value)
+        };
+        result.map_err(|e: value::Error| {
+            de::Error::custom(format!("{} in column `{}`", e, self.column))
+        })
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, value::Error> {
+        visitor.visit_string(self.value)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, value::Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, value::Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, value::Error> {
+        visitor.visit_some(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum ignored_any
+    }
+}
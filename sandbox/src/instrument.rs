@@ -0,0 +1,110 @@
+/*
+ * Operation counts of the algorithms, the abstract work they do besides the time it takes.
+ *
+ * The algorithms call `count` wherever they compare, push, hop or copy, which does nothing unless
+ * the current thread runs inside `measure`. The counts are kept per thread, so benchmarks and
+ * tests measuring at the same time do not mix them up.
+ */
+use serde::Serialize;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// two values compared, like temperatures or task ends
+    Comparison,
+    StackPush,
+    StackPop,
+    /// a step from a list node to the next one
+    NodeHop,
+    /// a combination checked against a ticket
+    SubsetCheck,
+    Clone,
+    /// a vector, set or node allocated by the algorithm itself
+    Allocation,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 7] = [
+        Operation::Comparison,
+        Operation::StackPush,
+        Operation::StackPop,
+        Operation::NodeHop,
+        Operation::SubsetCheck,
+        Operation::Clone,
+        Operation::Allocation,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Comparison => "comparison",
+            Operation::StackPush => "stack_push",
+            Operation::StackPop => "stack_pop",
+            Operation::NodeHop => "node_hop",
+            Operation::SubsetCheck => "subset_check",
+            Operation::Clone => "clone",
+            Operation::Allocation => "allocation",
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// How many operations of each kind were done, the kinds that were never done are left out
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OperationCounts(BTreeMap<Operation, u64>);
+
+impl OperationCounts {
+    pub fn get(&self, operation: Operation) -> u64 {
+        self.0.get(&operation).copied().unwrap_or(0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Operation, u64)> + '_ {
+        self.0.iter().map(|(&operation, &count)| (operation, count))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+thread_local! {
+    /// whether the thread runs inside `measure`, checked before the counts so that counting costs
+    /// a flag read outside of it
+    static MEASURING: Cell<bool> = const { Cell::new(false) };
+    static COUNTS: RefCell<Option<OperationCounts>> = const { RefCell::new(None) };
+}
+
+/// Counts `n` operations of a kind, if the current thread is being measured
+#[inline]
+pub fn count(operation: Operation, n: u64) {
+    if !MEASURING.get() {
+        return;
+    }
+    COUNTS.with(|counts| {
+        if let Some(counts) = counts.borrow_mut().as_mut() {
+            *counts.0.entry(operation).or_default() += n;
+        }
+    });
+}
+
+/**
+ * Runs `f` and returns what it returned along with the operations it counted. A measure inside
+ * another one counts for the inner one only.
+ */
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, OperationCounts) {
+    let outer = COUNTS.with(|counts| counts.replace(Some(OperationCounts::default())));
+    let was_measuring = MEASURING.replace(true);
+    let result = f();
+    MEASURING.set(was_measuring);
+    let measured = COUNTS.with(|counts| counts.replace(outer));
+    (result, measured.unwrap_or_default())
+}
@@ -1,134 +1,450 @@
-use clap::{value_parser, Subcommand};
-use log::{debug, info};
-use std::path::PathBuf; // path buffer, to construct paths
+use clap::ArgMatches;
+use log::{debug, info, warn};
+use std::fmt;
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf}; // path buffer, to construct paths
 
+mod allocations;
+mod batch;
+mod bench;
+mod cache;
+mod cancellation;
+mod catalog;
 mod commands;
+mod config;
+mod coverage_matrix;
+mod gantt;
+mod generators;
+mod input;
+mod instrument;
+mod progress;
+mod rpc;
+mod schema;
+mod server;
+mod trace;
 mod utils;
+mod watch;
 
-use commands::*;
-
-#[derive(Subcommand)]
-pub enum Commands {
-    /// Optimal Scheduling of picking tasks with overlapping intervals
-    Schedule {
-        /// Path to the JSON file containing the tasks in the form of
-        /// { "tasks": [
-        ///     { "start": 1, "end": 3, "name": "Task 1" },
-        ///     { "start": 2, "end": 4, "name": "Task 2" }
-        /// ] }
-        #[arg(short, long)]
-        json_file_path: PathBuf,
-    },
-    /// Sufficient Coverage Set Problem
-    SufficientCoverageSet {
-        /// Path to the JSON file containing the items in the form of
-        /// { "items": [
-        ///     { "tickets": [[1, 2], [2, 3], [3, 4]], "target_coverage": { "numbers": [1, 2, 3, 4], "min_numbers_to_cover": 2 }, "expected": true },
-        ///     { "tickets": [[1, 2], [2, 3], [3, 4]], "target_coverage": { "numbers": [1, 2, 3, 4], "min_numbers_to_cover": 3 }, "expected": false }
-        /// ] }
-        #[arg(short, long)]
-        json_file_path: PathBuf,
-    },
-    DailyTemperature {
-        /// numbers containing the temperatures separated by commas
-        /// 33, 74, 75, 71, 69, 72, 76, 73
-        #[arg(short, long, required = true, value_delimiter = ',', value_parser = value_parser!(i32))]
-        temperatures: Vec<i32>,
-
-        /// algorithm mode: either "stack" or "reverse"
-        #[arg(short, long)]
-        mode: String,
-    },
-    /// Rotating a list by k rotations
-    RotatingList {
-        /// numbers containing the temperatures separated by commas
-        /// 33, 74, 75, 71, 69, 72, 76, 73
-        #[arg(short, long, required = true, value_delimiter = ',', value_parser = value_parser!(i32))]
-        numbers: Vec<i32>,
-
-        /// algorithm mode: either "stack" or "reverse"
-        #[arg(short, long, value_parser = value_parser!(i32))]
-        k: i32,
-
-        /// algorithm mode: either "Right" or "Left"
-        #[arg(short, long, default_value = "right")]
-        rotation_direction: String,
-    },
-}
-
-impl ToString for Commands {
-    fn to_string(&self) -> String {
-        match self {
-            Commands::Schedule { json_file_path } => {
-                format!("Schedule {{ json_file_path: {:?} }}", json_file_path)
-            }
-            Commands::SufficientCoverageSet { json_file_path } => {
-                format!(
-                    "SufficientCoverageSet {{ json_file_path: {:?} }}",
-                    json_file_path
-                )
-            }
-            Commands::DailyTemperature { temperatures, mode } => {
-                format!(
-                    "DailyTemperature {{ temperatures: {:?}, mode: {:?} }}",
-                    temperatures, mode
-                )
-            }
-            Commands::RotatingList {
-                numbers,
-                k,
-                rotation_direction,
-            } => {
-                format!(
-                    "RotatingList {{ numbers: {:?}, k: {:?}, rotation_direction: {:?} }}",
-                    numbers, k, rotation_direction
+// the C API, public so the Rust side can call it too
+pub mod ffi;
+
+pub use allocations::{format_bytes, track as track_allocations, AllocationStats};
+pub use batch::{run_batch, BatchSummary};
+pub use bench::{bench_command_names, parse_size, run_bench, BenchReport, OperationGrowth};
+pub use cache::{cache_key, Cache, Pruned};
+pub use cancellation::{
+    cancel_on_interrupt, parse_duration, CancellationToken, Cancelled, StopReason, Stopped,
+};
+pub use catalog::{describe_text, list_commands, run_example, ExampleRun};
+pub use commands::{
+    describe_command, find_command, registry, Command, CommandContext, Example, Mode,
+};
+// the algorithms behind the commands, to use them without going through the command line
+pub use commands::daily_temperature::get_daily_temperature;
+pub use commands::job_sequencing::{sequence_jobs, JobSchedule, JobSequence};
+pub use commands::rotating_list::rotating_list;
+pub use commands::schedule::{get_optimal_schedule, Schedule};
+pub use commands::sufficient_coverage_set::{
+    evaluate_sufficient_coverage, SufficientCoverageProblemSet,
+};
+pub use config::{Config, ConfigSource, Setting};
+pub use coverage_matrix::{CoverageCell, CoverageChart, CoverageMatrix};
+pub use gantt::{BarStatus, GanttBar, GanttChart, GanttRow};
+pub use generators::{run_gen, GenInvocation};
+pub use input::{parse_input, InputFormat};
+pub use instrument::{measure, Operation, OperationCounts};
+pub use progress::{Progress, Stage};
+pub use rpc::run_rpc;
+pub use schema::{schema_for, validate};
+pub use server::Server;
+pub use trace::{Observer, ReplayState, Trace, TraceEvent, TraceRecorder, TracedTask, Tracer};
+pub use utils::{diff_json, diff_lines, is_quiet, quietly, Datasets};
+pub use watch::watch_command;
+
+/// Dataset name that inline JSON input is available under
+const INPUT_DATASET: &str = "input";
+
+/// A registered command along with the arguments it was invoked with
+pub struct Invocation {
+    command: &'static dyn Command,
+    matches: ArgMatches,
+}
+
+impl fmt::Display for Invocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args: Vec<String> = self
+            .matches
+            .ids()
+            .map(|id| {
+                let values: Vec<_> = self
+                    .matches
+                    .get_raw(id.as_str())
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                format!("{}: {:?}", id, values)
+            })
+            .collect();
+        write!(f, "{} {{ {} }}", self.command.name(), args.join(", "))
+    }
+}
+
+impl Invocation {
+    pub fn new(command: &'static dyn Command, matches: ArgMatches) -> Self {
+        Invocation { command, matches }
+    }
+
+    pub fn command(&self) -> &'static dyn Command {
+        self.command
+    }
+
+    /// Name of the subcommand, as typed on the command line
+    pub fn name(&self) -> &'static str {
+        self.command.name()
+    }
+
+    /// Path of the JSON input file, for commands that read one
+    pub fn json_file_path(&self) -> Option<&PathBuf> {
+        commands::json_file_path(&self.matches)
+    }
+}
+
+/// The subcommands of every registered command, to add to a clap parser
+pub fn clap_subcommands() -> Vec<clap::Command> {
+    registry()
+        .iter()
+        .map(|command| command.clap_command())
+        .collect()
+}
+
+/// The subcommands of every registered command, with their defaults taken from the config
+pub fn configured_clap_subcommands(config: &Config) -> Vec<clap::Command> {
+    registry()
+        .iter()
+        .map(|command| config.apply(command.clap_command()))
+        .collect()
+}
+
+/**
+ * Takes the subcommand out of the matches if it is a registered command, leaving the rest of the
+ * matches to be parsed by the caller.
+ */
+pub fn take_invocation(matches: &mut ArgMatches) -> Option<Invocation> {
+    let command = find_command(matches.subcommand_name()?)?;
+    let (_, sub_matches) = matches.remove_subcommand()?;
+    Some(Invocation::new(command, sub_matches))
+}
+
+/// Parses a command from its arguments, e.g. `["rotating-list", "-n", "1,2,3", "-k", "1"]`
+pub fn parse_command<I, T>(args: I) -> Result<Invocation, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let mut matches = clap::Command::new("dat_cli")
+        .no_binary_name(true)
+        .subcommand_required(true)
+        .subcommands(clap_subcommands())
+        .try_get_matches_from(args)?;
+    Ok(take_invocation(&mut matches).unwrap())
+}
+
+/// Command line value for an argument given as JSON, lists joined by commas
+fn json_to_arg(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        serde_json::Value::Array(values) => values
+            .iter()
+            .map(json_to_arg)
+            .collect::<Option<Vec<String>>>()
+            .map(|values| values.join(",")),
+        _ => None,
+    }
+}
+
+/**
+ * Builds an invocation from arguments given as JSON and keyed by argument id, e.g.
+ * `{"numbers": [1, 2, 3], "k": 1}` for `rotating-list`.
+ */
+pub fn parse_json_args(
+    command: &'static dyn Command,
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Invocation, Box<dyn std::error::Error>> {
+    let known_args = command.args();
+    let mut command_line = vec![command.name().to_string()];
+    for (id, value) in args {
+        let long = known_args
+            .iter()
+            .find(|arg| arg.get_id() == id.as_str())
+            .and_then(|arg| arg.get_long())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown argument {} for {}", id, command.name()),
                 )
-            }
+            })?;
+        let value = json_to_arg(value).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unsupported value for {}: {}", id, value),
+            )
+        })?;
+        // in one piece, so negative numbers are not taken for flags
+        command_line.push(format!("--{}={}", long, value));
+    }
+    Ok(parse_command(command_line)?)
+}
+
+/**
+ * Builds an invocation like `parse_json_args`, with the JSON input of a file based command given
+ * inline instead of as `json_file_path`. The input is returned as the `@input` dataset to run with.
+ */
+pub fn parse_json_request(
+    command: &'static dyn Command,
+    mut args: serde_json::Map<String, serde_json::Value>,
+    input: Option<serde_json::Value>,
+) -> Result<(Invocation, Datasets), Box<dyn std::error::Error>> {
+    let mut datasets = Datasets::new();
+    if let Some(input) = input {
+        if !command.takes_input() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} takes no input", command.name()),
+            )
+            .into());
+        }
+        if args.contains_key("json_file_path") {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "expected either an input or json_file_path, not both",
+            )
+            .into());
         }
+        datasets.insert(INPUT_DATASET.to_string(), input);
+        args.insert(
+            "json_file_path".to_string(),
+            serde_json::Value::String(format!("@{}", INPUT_DATASET)),
+        );
+    }
+    Ok((parse_json_args(command, &args)?, datasets))
+}
+
+/// The JSON Schema of the input file of a command, or of its result with `output`
+pub fn command_schema(
+    name: &str,
+    output: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let command = find_command(name)
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("no command named {}", name)))?;
+    if output {
+        return Ok(command.output_schema());
     }
+    command.input_schema().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} takes its input as arguments, not as a file, see --help",
+                name
+            ),
+        )
+        .into()
+    })
+}
+
+/**
+ * Validates an input file in any of the input formats against the schema of the command, returning
+ * every error with the path it is about. Empty if the file is valid.
+ *
+ * A CSV file is read into the input type of the command first, as only the fields tell whether a
+ * cell like `1` is a number or text. A cell that does not fit its field fails the read, with the
+ * line and column of the cell.
+ */
+pub fn validate_input_file(
+    name: &str,
+    path: &Path,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let schema = command_schema(name, false)?;
+    let typed = match input::InputFormat::from_path(path) {
+        input::InputFormat::Csv => find_command(name).and_then(|command| {
+            command.typed_input(&path.to_path_buf(), None, &CommandContext::default())
+        }),
+        _ => None,
+    };
+    let value: serde_json::Value = match typed {
+        Some(value) => value?,
+        None => input::parse_input_file(path, None)?,
+    };
+    Ok(validate(&schema, &value))
+}
+
+/// Loads an input file so it can be reused across commands as a dataset
+pub fn load_dataset(path: &Path) -> Result<serde_json::Value, std::io::Error> {
+    input::parse_input_file(path, None)
+}
+
+pub fn run_command(cmd: Invocation) -> Result<(), Box<dyn std::error::Error>> {
+    run_command_with_datasets(cmd, &Datasets::new())
+}
+
+/// Runs the command, resolving any `@name` JSON file path against the loaded datasets
+pub fn run_command_with_datasets(
+    cmd: Invocation,
+    datasets: &Datasets,
+) -> Result<(), Box<dyn std::error::Error>> {
+    run_command_with_tracer(cmd, datasets, Tracer::none(), &RunOptions::default())
+}
+
+/// How a run from the command line is traced, limited and watched
+#[derive(Debug, Default)]
+pub struct RunOptions {
+    /// file to write the events of the algorithm to
+    pub trace: Option<PathBuf>,
+    /// how long the run may take before it is cancelled
+    pub timeout: Option<std::time::Duration>,
+    /// whether to show the progress of long runs on stderr
+    pub show_progress: bool,
+    /// cancels the run from outside, like on Ctrl-C
+    pub cancellation: CancellationToken,
+    /// where to look for the result of the same run before running, and to keep it after
+    pub cache: Option<Cache>,
+}
+
+/**
+ * Runs the command like `run_command`, with the trace, timeout, progress and cache of the options. A
+ * run that is cancelled fails with `Stopped`, telling how far it got. Traced runs skip the cache, as
+ * a cached result has no events to trace.
+ */
+pub fn run_command_with_options(
+    cmd: Invocation,
+    options: &RunOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(trace_path) = &options.trace else {
+        return run_command_with_tracer(cmd, &Datasets::new(), Tracer::none(), options);
+    };
+    let recorder = TraceRecorder::default();
+    let name = cmd.name();
+    // the trace is written even when the command fails, it shows how far it got
+    let result = run_command_with_tracer(cmd, &Datasets::new(), Tracer::new(&recorder), options);
+    let trace = recorder.into_trace(name);
+    std::fs::write(trace_path, serde_json::to_string_pretty(&trace)?)?;
+    info!(
+        "Wrote {} trace events to {}",
+        trace.events.len(),
+        trace_path.display()
+    );
+    result
 }
 
-pub fn run_command(cmd: Commands) -> Result<(), Box<dyn std::error::Error>> {
-    match cmd {
-        Commands::Schedule { json_file_path } => {
-            // Implement the scheduling algorithm here
-            debug!("Scheduling tasks from {}", json_file_path.display());
-            schedule::get_optimal_schedule(&json_file_path)?;
-            Ok(())
+fn run_command_with_tracer(
+    cmd: Invocation,
+    datasets: &Datasets,
+    tracer: Tracer,
+    options: &RunOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let progress = Progress::new();
+    let context = CommandContext {
+        datasets,
+        base_dir: None,
+        cancellation: options.cancellation.clone(),
+        tracer,
+        progress: Some(&progress),
+    };
+    let cached = match &options.cache {
+        Some(cache) if cmd.command.cacheable() && options.trace.is_none() => {
+            cache_key(&cmd, &context)?.map(|key| (cache, key))
+        }
+        _ => None,
+    };
+    if let Some((cache, key)) = &cached {
+        if let Some(result) = cache.get(key) {
+            info!("Using the cached result {}", key);
+            return cmd.command.report(&result);
         }
-        Commands::SufficientCoverageSet { json_file_path } => {
-            // Implement the sufficient coverage set algorithm here
-            debug!(
-                "Finding sufficient coverage set from {}",
-                json_file_path.display()
-            );
-            sufficient_coverage_set::evaluate_sufficient_coverage(&json_file_path)?;
-            Ok(())
+    }
+    let time_elapsed = std::time::Instant::now();
+    let ((result, allocations), timed_out) = progress::watch(
+        &progress,
+        &options.cancellation,
+        options.timeout,
+        options.show_progress,
+        || allocations::track(|| execute_command(&cmd, &context)),
+    );
+    debug!("Time elapsed: {:?}", time_elapsed.elapsed());
+    if let Some(allocations) = &allocations {
+        info!("Heap usage: {}", allocations);
+    }
+    let result = match result {
+        Err(e) if Cancelled::caused(e.as_ref()) => {
+            let reason = match (timed_out, options.timeout) {
+                (true, Some(timeout)) => StopReason::Timeout(timeout),
+                _ => StopReason::Interrupted,
+            };
+            return Err(Stopped {
+                reason,
+                stages: progress.stages(),
+            }
+            .into());
         }
-        Commands::DailyTemperature { temperatures, mode } => {
-            // Implement the daily temperature algorithm here
-            info!(
-                "Finding daily temperature from {:?} using {:?} method",
-                temperatures, mode
-            );
-            let time_elapsed = std::time::Instant::now();
-            let result = daily_temperature::get_daily_temperature(&temperatures, &mode)?;
-            debug!("Time elapsed: {:?}", time_elapsed.elapsed());
-            info!("Result: {:?}", result);
-            Ok(())
+        result => result?,
+    };
+    if let Some((cache, key)) = cached {
+        match cache.put(&key, cmd.name(), &result) {
+            Ok(()) => debug!("Cached the result as {}", key),
+            Err(e) => warn!(
+                "Could not cache the result in {}: {}",
+                cache.dir().display(),
+                e
+            ),
         }
-        Commands::RotatingList {
-            numbers,
-            k,
-            rotation_direction,
-        } => {
-            // Implement the rotating list algorithm here
-            info!("Rotating list from {:?} by {:?} rotations", numbers, k);
-            let time_elapsed = std::time::Instant::now();
-            let result = rotating_list::rotating_list(numbers, k, &rotation_direction)?;
-            debug!("Time elapsed: {:?}", time_elapsed.elapsed());
-            info!("Result: {:?}", result);
-            Ok(())
+    }
+    match allocations {
+        // with the track-allocations feature, the heap usage of the run goes along with its result
+        // as in the HTTP server, unless the result is a rendered chart rather than JSON
+        Some(allocations) if !result.is_string() => cmd.command.report(&serde_json::json!({
+            "command": cmd.name(),
+            "result": result,
+            "allocations": allocations,
+        })),
+        _ => cmd.command.report(&result),
+    }
+}
+
+/**
+ * Replays a trace file written with `--trace`, printing each event along with the state of the
+ * algorithm after it. With `step`, waits for Enter before each event, and stops on `q`.
+ */
+pub fn run_replay(path: &Path, step: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let trace: Trace = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let mut state = ReplayState::default();
+    let stdin = std::io::stdin();
+    println!("{}: {} events", trace.command, trace.events.len());
+    for (i, event) in trace.events.iter().enumerate() {
+        if step {
+            print!("-- press Enter for the next step, q to quit ");
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 || line.trim() == "q" {
+                break;
+            }
         }
+        state.apply(event).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("{}: event {}: {}", path.display(), i + 1, e),
+            )
+        })?;
+        println!("\n[{}/{}] {}", i + 1, trace.events.len(), event);
+        print!("{}", state);
     }
+    Ok(())
+}
+
+/// Runs the command and returns its result as JSON instead of reporting it
+pub fn execute_command(
+    cmd: &Invocation,
+    context: &CommandContext,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    cmd.command.run(&cmd.matches, context)
 }
@@ -1,28 +1,165 @@
-use log::LevelFilter;
-use log4rs::append::console::ConsoleAppender;
-use log4rs::config::{Appender, Config, Root};
+use log::{Level, LevelFilter, Record};
+use log4rs::append::console::{ConsoleAppender, Target};
+use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
+use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
+use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
+use log4rs::append::rolling_file::RollingFileAppender;
+use log4rs::config::{Appender, Config, Logger, Root};
+use log4rs::encode::json::JsonEncoder;
 use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::Encode;
+use log4rs::filter::{Filter, Response};
+use log4rs::Handle;
+use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-pub fn init_logging(debug: bool) {
-    let level_filter = if debug {
-        LevelFilter::Debug
+/// Environment variable with per-module levels, e.g. `dat_cli::commands=debug,rustyline=warn`
+pub const LOG_LEVELS_ENV: &str = "DAT_CLI_LOG";
+
+const PATTERN: &str = "{d(%+)(local)} [{t}] {h({l})} -- {m}{n}";
+
+pub struct LoggingOptions {
+    pub level: LevelFilter,
+    /// raise the level to at least debug, toggled from the REPL
+    pub debug: bool,
+    pub json: bool,
+    pub log_file: Option<PathBuf>,
+    /// size in bytes after which the log file is rolled over
+    pub log_file_size: u64,
+    /// number of rolled over log files to keep
+    pub log_file_count: u32,
+}
+
+impl LoggingOptions {
+    /// Level from the number of `-v` and `-q` flags, info by default
+    pub fn level_from_verbosity(verbose: u8, quiet: u8) -> LevelFilter {
+        match verbose as i16 - quiet as i16 {
+            i16::MIN..=-3 => LevelFilter::Off,
+            -2 => LevelFilter::Error,
+            -1 => LevelFilter::Warn,
+            0 => LevelFilter::Info,
+            1 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+
+    fn root_level(&self) -> LevelFilter {
+        if self.debug {
+            self.level.max(LevelFilter::Debug)
+        } else {
+            self.level
+        }
+    }
+}
+
+/// Drops everything below warnings logged from within `dat_cli::quietly`
+#[derive(Debug)]
+struct QuietFilter;
+
+impl Filter for QuietFilter {
+    fn filter(&self, record: &Record) -> Response {
+        match record.level() > Level::Warn && dat_cli::is_quiet() {
+            true => Response::Reject,
+            false => Response::Neutral,
+        }
+    }
+}
+
+fn encoder(json: bool) -> Box<dyn Encode> {
+    if json {
+        Box::new(JsonEncoder::new())
     } else {
-        LevelFilter::Info
-    };
+        Box::new(PatternEncoder::new(PATTERN))
+    }
+}
+
+/// Parses the per-module levels, a bare level applies to every module
+fn module_levels(spec: &str) -> (Option<LevelFilter>, Vec<(String, LevelFilter)>) {
+    let mut root_level = None;
+    let mut levels = Vec::new();
+    for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let parsed = match directive.split_once('=') {
+            Some((module, level)) => LevelFilter::from_str(level.trim())
+                .map(|level| levels.push((module.trim().to_string(), level))),
+            None => LevelFilter::from_str(directive).map(|level| root_level = Some(level)),
+        };
+        if parsed.is_err() {
+            // the logger is not up yet
+            eprintln!(
+                "Ignoring invalid {} directive: {}",
+                LOG_LEVELS_ENV, directive
+            );
+        }
+    }
+    (root_level, levels)
+}
+
+fn build_config(options: &LoggingOptions) -> Result<Config, Box<dyn Error>> {
+    // logs go to stderr, so the output of the commands stays clean
     let console_config = ConsoleAppender::builder()
-        .encoder(Box::new(PatternEncoder::new(
-            "{d(%+)(local)} [{t}] {h({l})} -- {m}{n}",
-        )))
+        .target(Target::Stderr)
+        .encoder(encoder(options.json))
         .build();
+    let mut config = Config::builder().appender(
+        Appender::builder()
+            .filter(Box::new(QuietFilter))
+            .build("console_stderr", Box::new(console_config)),
+    );
+    let mut root = Root::builder().appender("console_stderr");
+
+    if let Some(log_file) = &options.log_file {
+        let roller = FixedWindowRoller::builder()
+            .build(
+                &format!("{}.{{}}", log_file.display()),
+                options.log_file_count,
+            )
+            .map_err(|e| format!("cannot roll over {}: {}", log_file.display(), e))?;
+        let policy = CompoundPolicy::new(
+            Box::new(SizeTrigger::new(options.log_file_size)),
+            Box::new(roller),
+        );
+        let file_config = RollingFileAppender::builder()
+            .encoder(encoder(options.json))
+            .build(log_file, Box::new(policy))
+            .map_err(|e| format!("cannot write the log file {}: {}", log_file.display(), e))?;
+        config = config.appender(
+            Appender::builder()
+                .filter(Box::new(QuietFilter))
+                .build("log_file", Box::new(file_config)),
+        );
+        root = root.appender("log_file");
+    }
+
+    let mut root_level = options.root_level();
+    if let Ok(spec) = std::env::var(LOG_LEVELS_ENV) {
+        let (env_root_level, levels) = module_levels(&spec);
+        root_level = env_root_level.unwrap_or(root_level);
+        for (module, level) in levels {
+            config = config.logger(Logger::builder().build(module, level));
+        }
+    }
 
-    let config = Config::builder()
-        .appender(Appender::builder().build("console_stdout", Box::new(console_config)))
-        .build(
-            Root::builder()
-                .appender("console_stdout")
-                .build(level_filter),
-        )
-        .unwrap();
+    Ok(config.build(root.build(root_level))?)
+}
+
+/// Sets up the logger, failing when the log file cannot be written
+pub fn init_logging(options: LoggingOptions) -> Result<Logging, Box<dyn Error>> {
+    let handle = log4rs::init_config(build_config(&options)?)?;
+    Ok(Logging { handle, options })
+}
+
+/// Handle to the initialized logger, to reconfigure it while running
+pub struct Logging {
+    handle: Handle,
+    options: LoggingOptions,
+}
 
-    log4rs::init_config(config).unwrap();
+impl Logging {
+    /// Switches debug logging on or off
+    pub fn set_debug(&mut self, debug: bool) -> Result<(), Box<dyn Error>> {
+        self.options.debug = debug;
+        self.handle.set_config(build_config(&self.options)?);
+        Ok(())
+    }
 }
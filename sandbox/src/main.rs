@@ -1,8 +1,14 @@
-use clap::Parser; // CLI parser
-use dat_cli::Commands;
-use log::debug;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand}; // CLI parser
+use clap_complete::Shell;
+use dat_cli::{Config, GenInvocation};
+use log::{debug, warn};
+use std::fmt;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Duration;
 
 mod logging;
+mod repl;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -11,25 +17,534 @@ struct Cli {
     #[arg(short, long)]
     debug: bool,
 
+    /// Log more, -v for debug and -vv for trace
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Log less, -q for warnings, -qq for errors and -qqq for nothing
+    #[arg(short, long, action = ArgAction::Count)]
+    quiet: u8,
+
+    /// Also write the logs to this file, rolling it over once it gets too big
+    #[arg(long)]
+    log_file: Option<PathBuf>,
+
+    /// Size in bytes after which the log file is rolled over
+    #[arg(long, default_value_t = 10 * 1024 * 1024)]
+    log_file_size: u64,
+
+    /// Number of rolled over log files to keep
+    #[arg(long, default_value_t = 5)]
+    log_file_count: u32,
+
+    /// Log format: either "text" or "json" (one JSON object per line)
+    #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+    log_format: String,
+
+    /// Record the events of the algorithm a command runs to this file, to replay them later
+    #[arg(long, global = true, value_name = "FILE")]
+    trace: Option<PathBuf>,
+
+    /// Run a file based command again whenever its input file changes, printing what changed
+    #[arg(long, global = true)]
+    watch: bool,
+
+    /// Stop a command that runs longer than this, e.g. 30s, 500ms or 2m, and report how far it got
+    #[arg(long, global = true, value_name = "DURATION", value_parser = dat_cli::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Keep the results of the file based commands in the cache directory and reuse them when the
+    /// same problem is run again
+    #[arg(long, global = true, env = "DAT_CLI_CACHE", value_parser = clap::builder::BoolishValueParser::new())]
+    cache: bool,
+
+    /// Run without the cache, even when --cache or DAT_CLI_CACHE turns it on
+    #[arg(long, global = true)]
+    no_cache: bool,
+
     #[command(subcommand)]
-    command: Option<Commands>,
+    command: Option<CliCommands>,
 }
 
-impl ToString for Cli {
-    fn to_string(&self) -> String {
+#[derive(Subcommand)]
+enum CliCommands {
+    /// Interactive session running commands line by line
+    Repl,
+    /// Run a JSON or TOML manifest of commands and compare against their expected results
+    Batch {
+        /// Path to the manifest file
+        manifest: PathBuf,
+    },
+    /// Benchmark every mode of a command over generated inputs
+    Bench {
+        /// command to benchmark
+        #[arg(value_parser = dat_cli::bench_command_names())]
+        command: String,
+
+        /// input sizes separated by commas, like 1e3,1e4,1e5, by default sizes that suit the command
+        #[arg(short, long, value_delimiter = ',', value_parser = dat_cli::parse_size)]
+        sizes: Option<Vec<usize>>,
+
+        /// number of runs per mode and size
+        #[arg(short, long, default_value_t = 10)]
+        iterations: usize,
+
+        /// seed for generating the inputs
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// report format: either "table", "csv" or "json"
+        #[arg(short, long, default_value = "table", value_parser = ["table", "csv", "json"])]
+        format: String,
+
+        /// write the report to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// run on the worst case input instead of a random one, for the commands that have one
+        #[arg(long)]
+        worst_case: bool,
+
+        /// also count the operations of the algorithms, like comparisons and clones, and fit
+        /// their growth
+        #[arg(long)]
+        instrument: bool,
+    },
+    /// Serve the commands as JSON endpoints over HTTP
+    Serve {
+        /// port to listen on, 0 picks a free one
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+
+        /// address to listen on, only this machine by default
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// number of requests handled at the same time
+        #[arg(long, default_value_t = 4)]
+        workers: usize,
+    },
+    /// Speak line-delimited JSON-RPC 2.0 on stdin and stdout
+    Rpc,
+    /// Generate random inputs for a command
+    Gen {
+        /// seed for the generator, the same seed always generates the same input
+        #[arg(long, default_value_t = 0, global = true)]
+        seed: u64,
+
+        /// write the input to a file instead of stdout
+        #[arg(short = 'O', long, global = true)]
+        output: Option<PathBuf>,
+
+        #[command(subcommand)]
+        kind: GenInvocation,
+    },
+    /// Print the completion script for a shell
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page, or write one page per subcommand into a directory
+    Man {
+        /// directory to write the man pages to
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// List the commands along with what they solve
+    List,
+    /// Print the problem a command solves, its modes with their complexity and an example
+    Describe {
+        /// command to describe
+        #[arg(value_parser = dat_cli::registry().iter().map(|command| command.name()).collect::<Vec<_>>())]
+        command: String,
+
+        /// run the example and check it gives the documented result
+        #[arg(long)]
+        run_example: bool,
+    },
+    /// Print the JSON Schema of the input file of a command, or validate a file against it
+    Schema {
+        /// command to print the schema of
+        #[arg(value_parser = dat_cli::registry().iter().map(|command| command.name()).collect::<Vec<_>>())]
+        command: String,
+
+        /// print the schema of the result instead of the input
+        #[arg(long)]
+        output: bool,
+
+        /// check this input file against the schema instead of printing it, - for stdin
+        #[arg(long, value_name = "FILE", conflicts_with = "output")]
+        validate: Option<PathBuf>,
+    },
+    /// Step through the events of a trace file written with --trace
+    Replay {
+        /// Path to the trace file
+        file: PathBuf,
+
+        /// wait for Enter before each event, q quits
+        #[arg(short, long)]
+        step: bool,
+    },
+    /// Inspect the defaults loaded from the config files and environment variables
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Manage the results kept by --cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective settings and where each one came from
+    Show,
+}
+
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// Remove cached results, all of them unless --older-than is given
+    Prune {
+        /// only remove the results cached longer ago than this, e.g. 7d or 12h
+        #[arg(long, value_name = "DURATION", value_parser = dat_cli::parse_duration)]
+        older_than: Option<Duration>,
+    },
+}
+
+impl fmt::Display for CliCommands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliCommands::Repl => write!(f, "Repl"),
+            CliCommands::Batch { manifest } => write!(f, "Batch {{ manifest: {:?} }}", manifest),
+            CliCommands::Bench {
+                command,
+                sizes,
+                iterations,
+                seed,
+                format,
+                output,
+                worst_case,
+                instrument,
+            } => write!(
+                f,
+                "Bench {{ command: {:?}, sizes: {:?}, iterations: {:?}, seed: {:?}, format: {:?}, output: {:?}, worst_case: {:?}, instrument: {:?} }}",
+                command, sizes, iterations, seed, format, output, worst_case, instrument
+            ),
+            CliCommands::Serve {
+                port,
+                host,
+                workers,
+            } => write!(
+                f,
+                "Serve {{ port: {:?}, host: {:?}, workers: {:?} }}",
+                port, host, workers
+            ),
+            CliCommands::Rpc => write!(f, "Rpc"),
+            CliCommands::Gen { seed, output, .. } => {
+                write!(f, "Gen {{ seed: {:?}, output: {:?} }}", seed, output)
+            }
+            CliCommands::Completions { shell } => write!(f, "Completions {{ shell: {:?} }}", shell),
+            CliCommands::Man { output_dir } => write!(f, "Man {{ output_dir: {:?} }}", output_dir),
+            CliCommands::List => write!(f, "List"),
+            CliCommands::Describe {
+                command,
+                run_example,
+            } => write!(
+                f,
+                "Describe {{ command: {:?}, run_example: {:?} }}",
+                command, run_example
+            ),
+            CliCommands::Schema {
+                command,
+                output,
+                validate,
+            } => write!(
+                f,
+                "Schema {{ command: {:?}, output: {:?}, validate: {:?} }}",
+                command, output, validate
+            ),
+            CliCommands::Replay { file, step } => {
+                write!(f, "Replay {{ file: {:?}, step: {:?} }}", file, step)
+            }
+            CliCommands::Config {
+                action: ConfigCommands::Show,
+            } => write!(f, "Config {{ action: Show }}"),
+            CliCommands::Cache {
+                action: CacheCommands::Prune { older_than },
+            } => write!(
+                f,
+                "Cache {{ action: Prune {{ older_than: {:?} }} }}",
+                older_than
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Cli {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut output = String::new();
         if let Some(cmd) = &self.command {
             output.push_str(&format!("{:?}", cmd.to_string()));
         }
-        format!("{:?}", output)
+        write!(f, "{:?}", output)
     }
 }
 
+/// The full CLI, the algorithm subcommands come from the command registry with defaults from the config
+fn cli_command(config: &Config) -> clap::Command {
+    Cli::command().subcommands(dat_cli::configured_clap_subcommands(config))
+}
+
+/// The result cache in the XDG cache directory, None with a warning if there is no such directory
+fn cache_or_warn() -> Option<dat_cli::Cache> {
+    let cache = dat_cli::Cache::in_default_dir();
+    if cache.is_none() {
+        warn!("Neither XDG_CACHE_HOME nor HOME is set, running without the cache");
+    }
+    cache
+}
+
 fn main() {
-    let args = Cli::parse();
-    logging::init_logging(args.debug);
+    // before the logger is up, which only comes after parsing the arguments the config applies to
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(2);
+    });
+    let mut matches = cli_command(&config).get_matches();
+    let invocation = dat_cli::take_invocation(&mut matches);
+    let args = Cli::from_arg_matches_mut(&mut matches).unwrap_or_else(|e| e.exit());
+    let logging = logging::init_logging(logging::LoggingOptions {
+        level: logging::LoggingOptions::level_from_verbosity(args.verbose, args.quiet),
+        debug: args.debug,
+        json: args.log_format == "json",
+        log_file: args.log_file.clone(),
+        log_file_size: args.log_file_size,
+        log_file_count: args.log_file_count,
+    })
+    .unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
     debug!("Args passed successfully: {:?}", args.to_string());
-    if let Some(cmd) = args.command {
-        dat_cli::run_command(cmd).unwrap();
+    for warning in config.warnings() {
+        warn!("{}", warning);
+    }
+
+    if let Some(invocation) = invocation {
+        debug!("Running {}", invocation);
+        if args.watch && args.trace.is_some() {
+            cli_command(&config)
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "--watch cannot be used with --trace",
+                )
+                .exit();
+        }
+        if args.watch {
+            let stop = dat_cli::CancellationToken::new();
+            dat_cli::cancel_on_interrupt(&stop);
+            if let Err(e) = dat_cli::watch_command(&invocation, &mut std::io::stdout(), &stop) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        let options = dat_cli::RunOptions {
+            trace: args.trace.clone(),
+            timeout: args.timeout,
+            show_progress: args.quiet == 0 && std::io::stderr().is_terminal(),
+            cancellation: dat_cli::CancellationToken::new(),
+            cache: match args.cache && !args.no_cache {
+                true => cache_or_warn(),
+                false => None,
+            },
+        };
+        dat_cli::cancel_on_interrupt(&options.cancellation);
+        let result = dat_cli::run_command_with_options(invocation, &options);
+        if let Some(stopped) = result
+            .as_ref()
+            .err()
+            .and_then(|e| e.downcast_ref::<dat_cli::Stopped>())
+        {
+            eprintln!("{}", stopped);
+            // the exit codes of timeout(1) and of a shell interrupted by Ctrl-C
+            std::process::exit(match stopped.reason {
+                dat_cli::StopReason::Timeout(_) => 124,
+                dat_cli::StopReason::Interrupted => 130,
+            });
+        }
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    match args.command {
+        Some(CliCommands::Repl) => repl::run_repl(logging, &config).unwrap(),
+        Some(CliCommands::Batch { manifest }) => {
+            let summary = dat_cli::run_batch(&manifest).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            });
+            if !summary.is_success() {
+                std::process::exit(1);
+            }
+        }
+        Some(CliCommands::Bench {
+            command,
+            sizes,
+            iterations,
+            seed,
+            format,
+            output,
+            worst_case,
+            instrument,
+        }) => {
+            let report = dat_cli::run_bench(
+                &command,
+                sizes.as_deref(),
+                iterations,
+                seed,
+                worst_case,
+                instrument,
+            )
+            .unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            });
+            let content = match format.as_str() {
+                "csv" => report.to_csv(),
+                "json" => serde_json::to_string_pretty(&report).unwrap(),
+                _ => report.to_string(),
+            };
+            match output {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, content) {
+                        eprintln!("error: {}: {}", path.display(), e);
+                        std::process::exit(1);
+                    }
+                }
+                None => print!("{}", content),
+            }
+        }
+        Some(CliCommands::Serve {
+            port,
+            host,
+            workers,
+        }) => {
+            let server = dat_cli::Server::bind((host.as_str(), port)).unwrap();
+            // on stdout, so scripts can pick up the port when it was picked by the system
+            println!("Listening on http://{}", server.local_addr());
+            server.run(workers);
+        }
+        Some(CliCommands::Rpc) => {
+            dat_cli::run_rpc(std::io::stdin().lock(), std::io::stdout()).unwrap()
+        }
+        Some(CliCommands::Gen { seed, output, kind }) => {
+            let content = dat_cli::run_gen(kind, seed).unwrap_or_else(|e| {
+                cli_command(&config)
+                    .error(clap::error::ErrorKind::ValueValidation, e)
+                    .exit()
+            });
+            match output {
+                Some(path) => std::fs::write(path, content + "\n").unwrap(),
+                None => println!("{}", content),
+            }
+        }
+        Some(CliCommands::Completions { shell }) => {
+            let mut cmd = cli_command(&config);
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        Some(CliCommands::Man { output_dir }) => {
+            let cmd = cli_command(&config);
+            match output_dir {
+                Some(dir) => {
+                    std::fs::create_dir_all(&dir).unwrap();
+                    clap_mangen::generate_to(cmd, &dir).unwrap();
+                }
+                None => clap_mangen::Man::new(cmd)
+                    .render(&mut std::io::stdout())
+                    .unwrap(),
+            }
+        }
+        Some(CliCommands::List) => print!("{}", dat_cli::list_commands(&dat_cli::registry())),
+        Some(CliCommands::Describe {
+            command,
+            run_example,
+        }) => {
+            let command = dat_cli::find_command(&command).unwrap();
+            print!("{}", dat_cli::describe_text(command));
+            if !run_example {
+                return;
+            }
+            let run = dat_cli::run_example(command).unwrap();
+            if run.passed() {
+                println!("\nThe example gives the documented result");
+                return;
+            }
+            println!("\nThe example does not give the documented result:");
+            for line in run.diff() {
+                println!("{}", line);
+            }
+            std::process::exit(1);
+        }
+        Some(CliCommands::Schema {
+            command,
+            output,
+            validate: None,
+        }) => {
+            let schema = dat_cli::command_schema(&command, output).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            });
+            println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+        }
+        Some(CliCommands::Schema {
+            command,
+            validate: Some(file),
+            ..
+        }) => {
+            let errors = dat_cli::validate_input_file(&command, &file).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            });
+            if errors.is_empty() {
+                println!("{}: valid", file.display());
+                return;
+            }
+            for error in errors.iter() {
+                println!("{}: {}", file.display(), error);
+            }
+            std::process::exit(1);
+        }
+        Some(CliCommands::Replay { file, step }) => {
+            if let Err(e) = dat_cli::run_replay(&file, step) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(CliCommands::Config {
+            action: ConfigCommands::Show,
+        }) => {
+            if config.files().is_empty() {
+                println!("# no config files found");
+            }
+            for path in config.files() {
+                println!("# loaded {}", path.display());
+            }
+            print!("{}", config);
+        }
+        Some(CliCommands::Cache {
+            action: CacheCommands::Prune { older_than },
+        }) => {
+            let Some(cache) = cache_or_warn() else {
+                std::process::exit(1);
+            };
+            println!("{}", cache.prune(older_than).unwrap());
+        }
+        None => {}
     }
 }
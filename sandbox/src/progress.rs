@@ -0,0 +1,218 @@
+/*
+ * Progress of long running commands, shown on stderr while they run and reported when they stop
+ * early.
+ *
+ * A command splits its work into stages up front, like the problems of a coverage set, and counts
+ * the units of each stage as it goes. The counting is a relaxed atomic increment, cheap enough for
+ * the innermost loops, and the display reads it from a thread of its own.
+ */
+use serde::Serialize;
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::cancellation::CancellationToken;
+
+/// How often the progress line is redrawn
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+/// Runs shorter than this never show a progress line, so quick commands do not flicker
+const SHOW_AFTER: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Stage {
+    pub label: String,
+    /// what is counted, like "combos"
+    pub unit: &'static str,
+    pub done: u64,
+    pub total: u64,
+    pub started: bool,
+    /// how the stage ended, None while it is running or not started
+    pub outcome: Option<String>,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.outcome, self.started) {
+            (Some(outcome), _) => write!(
+                f,
+                "{}: {}, {} of {} {} checked",
+                self.label, outcome, self.done, self.total, self.unit
+            ),
+            (None, true) => write!(
+                f,
+                "{}: stopped after {} of {} {}",
+                self.label, self.done, self.total, self.unit
+            ),
+            (None, false) => write!(f, "{}: not started", self.label),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Progress {
+    stages: Mutex<Vec<Stage>>,
+    /// index of the running stage, `usize::MAX` before the first one starts
+    current: AtomicUsize,
+    /// units done in the running stage
+    done: AtomicU64,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            stages: Mutex::default(),
+            current: AtomicUsize::new(usize::MAX),
+            done: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Progress::default()
+    }
+
+    pub fn add_stage(&self, label: String, unit: &'static str, total: u64) {
+        self.stages.lock().unwrap().push(Stage {
+            label,
+            unit,
+            done: 0,
+            total,
+            started: false,
+            outcome: None,
+        });
+    }
+
+    pub fn start(&self, stage: usize) {
+        let mut stages = self.stages.lock().unwrap();
+        stages[stage].started = true;
+        self.done.store(0, Ordering::Relaxed);
+        self.current.store(stage, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Ends the running stage with its outcome, like "covered"
+    pub fn finish(&self, outcome: impl Into<String>) {
+        let mut stages = self.stages.lock().unwrap();
+        let current = self.current.load(Ordering::Relaxed);
+        if let Some(stage) = stages.get_mut(current) {
+            stage.done = self.done.load(Ordering::Relaxed);
+            stage.outcome = Some(outcome.into());
+        }
+    }
+
+    /// Every stage as it is now, the running one with its units done so far
+    pub fn stages(&self) -> Vec<Stage> {
+        let mut stages = self.stages.lock().unwrap().clone();
+        let current = self.current.load(Ordering::Relaxed);
+        if let Some(stage) = stages.get_mut(current) {
+            if stage.outcome.is_none() {
+                stage.done = self.done.load(Ordering::Relaxed);
+            }
+        }
+        stages
+    }
+
+    /// The running stage as a line for the terminal, None before the first stage starts
+    fn line(&self) -> Option<String> {
+        let stages = self.stages();
+        let current = self.current.load(Ordering::Relaxed);
+        let stage = stages.get(current)?;
+        let percent = match stage.total {
+            0 => 100.0,
+            total => stage.done as f64 * 100.0 / total as f64,
+        };
+        Some(format!(
+            "[{}/{}] {}: {}/{} {} ({:.0}%)",
+            current + 1,
+            stages.len(),
+            stage.label,
+            stage.done,
+            stage.total,
+            stage.unit,
+            percent
+        ))
+    }
+}
+
+/// Number of ways to pick `k` of `n`, saturating at `u64::MAX`
+pub fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    let mut result: u128 = 1;
+    for i in 0..k {
+        // exact at every step, as the product of i + 1 consecutive numbers divides by (i + 1)!
+        result = result * (n - i) as u128 / (i + 1) as u128;
+        if result > u64::MAX as u128 {
+            return u64::MAX;
+        }
+    }
+    result as u64
+}
+
+/**
+ * Runs `run` while a thread of its own watches it: cancels it once `timeout` has passed, and
+ * redraws the progress line on stderr if `show` is set. Tells along with the result whether the
+ * run timed out.
+ */
+pub fn watch<R>(
+    progress: &Progress,
+    cancellation: &CancellationToken,
+    timeout: Option<Duration>,
+    show: bool,
+    run: impl FnOnce() -> R,
+) -> (R, bool) {
+    if timeout.is_none() && !show {
+        return (run(), false);
+    }
+    let (stop, stopped) = mpsc::channel::<()>();
+    let started = Instant::now();
+    thread::scope(|scope| {
+        let watcher = scope.spawn(move || {
+            let mut shown = false;
+            let mut timed_out = false;
+            loop {
+                let wait = match timeout {
+                    Some(timeout) if !timed_out => timeout
+                        .saturating_sub(started.elapsed())
+                        .min(REDRAW_INTERVAL),
+                    _ => REDRAW_INTERVAL,
+                };
+                if stopped.recv_timeout(wait) != Err(RecvTimeoutError::Timeout) {
+                    break;
+                }
+                if !timed_out && timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                    timed_out = true;
+                    cancellation.cancel();
+                }
+                if show && started.elapsed() >= SHOW_AFTER {
+                    if let Some(line) = progress.line() {
+                        eprint!("\r\x1b[2K{}", line);
+                        let _ = io::stderr().flush();
+                        shown = true;
+                    }
+                }
+            }
+            if shown {
+                eprint!("\r\x1b[2K");
+            }
+            timed_out
+        });
+        let result = run();
+        drop(stop);
+        (result, watcher.join().unwrap_or(false))
+    })
+}
@@ -0,0 +1,169 @@
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use dat_cli::{Config, Datasets, Invocation};
+use log::{error, info, warn};
+use rustyline::{error::ReadlineError, DefaultEditor};
+use std::path::PathBuf;
+
+use crate::logging::Logging;
+
+const PROMPT: &str = "dat_cli> ";
+const HISTORY_FILE: &str = ".dat_cli_history";
+
+// A single line typed into the REPL
+#[derive(Parser)]
+#[command(no_binary_name = true, disable_version_flag = true)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: ReplCommands,
+}
+
+#[derive(Subcommand)]
+enum ReplCommands {
+    /// Load a JSON file as a dataset, which commands can then reference as `@name`
+    Load {
+        /// Name to reference the dataset by
+        name: String,
+        /// Path to the JSON file
+        path: PathBuf,
+    },
+    /// Drop a loaded dataset
+    Unload {
+        /// Name of the dataset
+        name: String,
+    },
+    /// List the loaded datasets
+    Datasets,
+    /// Turn debugging information on or off
+    Debug {
+        #[arg(value_parser = ["on", "off"])]
+        state: String,
+    },
+    /// Show the lines entered so far
+    History,
+    /// Leave the session
+    #[command(alias = "quit")]
+    Exit,
+}
+
+struct Session {
+    datasets: Datasets,
+    logging: Logging,
+}
+
+impl Session {
+    fn run(&self, invocation: Invocation) {
+        if let Err(e) = dat_cli::run_command_with_datasets(invocation, &self.datasets) {
+            error!("{}", e);
+        }
+    }
+
+    /// Runs a single REPL command, returning false once the session should end
+    fn execute(&mut self, command: ReplCommands, editor: &DefaultEditor) -> bool {
+        match command {
+            ReplCommands::Load { name, path } => {
+                match dat_cli::load_dataset(&path) {
+                    Ok(value) => {
+                        info!("Loaded {} as @{}", path.display(), name);
+                        self.datasets.insert(name, value);
+                    }
+                    Err(e) => error!("Failed to load {}: {}", path.display(), e),
+                };
+            }
+            ReplCommands::Unload { name } => {
+                if self.datasets.remove(&name).is_none() {
+                    error!("Unknown dataset: {}", name);
+                }
+            }
+            ReplCommands::Datasets => {
+                let mut names: Vec<&String> = self.datasets.keys().collect();
+                names.sort();
+                for name in names {
+                    println!("@{}", name);
+                }
+            }
+            ReplCommands::Debug { state } => {
+                if let Err(e) = self.logging.set_debug(state == "on") {
+                    error!("{}", e);
+                }
+            }
+            ReplCommands::History => {
+                for (i, line) in editor.history().iter().enumerate() {
+                    println!("{:>4}  {}", i + 1, line);
+                }
+            }
+            ReplCommands::Exit => return false,
+        }
+        true
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+pub fn run_repl(logging: Logging, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut editor = DefaultEditor::new()?;
+    let history_path = history_path();
+    if let Some(path) = &history_path {
+        // no history yet is fine
+        let _ = editor.load_history(path);
+    }
+
+    let mut session = Session {
+        datasets: Datasets::new(),
+        logging,
+    };
+
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            // Ctrl-C only clears the current line
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+
+        let Some(args) = shlex::split(line) else {
+            error!("Unbalanced quotes in: {}", line);
+            continue;
+        };
+        let mut matches = match ReplLine::command()
+            .subcommands(dat_cli::configured_clap_subcommands(config))
+            .try_get_matches_from(args)
+        {
+            Ok(matches) => matches,
+            Err(e) => {
+                // also covers `help` and `--help`
+                let _ = e.print();
+                continue;
+            }
+        };
+        if let Some(invocation) = dat_cli::take_invocation(&mut matches) {
+            session.run(invocation);
+            continue;
+        }
+        let command = match ReplLine::from_arg_matches_mut(&mut matches) {
+            Ok(parsed) => parsed.command,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+        if !session.execute(command, &editor) {
+            break;
+        }
+    }
+
+    if let Some(path) = &history_path {
+        // losing the history is no reason to fail the session
+        if let Err(e) = editor.save_history(path) {
+            warn!("Could not save the history to {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
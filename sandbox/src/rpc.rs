@@ -0,0 +1,257 @@
+/*
+ * Line-delimited JSON-RPC 2.0: one request per line in, one response per line out.
+ *
+ * Methods:
+ *  listCommands                     every command and its arguments
+ *  describeCommand { name }         a single command
+ *  <command> { input?, ...args }    runs a command, e.g. `job-sequencing` with
+ *                                   `{ "input": { "items": [...] }, "mode": "profit" }`
+ *  $/cancelRequest { id }           cancels a running request, which then fails with -32800
+ *
+ * Commands run in the background, so their responses can come back out of order and a long
+ * running one can be cancelled while it runs. Requests without an id are notifications and get
+ * no response.
+ *
+ * With the track-allocations feature, the result of a command is
+ * `{ "command", "result", "allocations" }` as in the HTTP server, with the heap usage of the run.
+ */
+use log::{debug, error};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Error, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::Mutex,
+    thread,
+};
+
+use crate::allocations;
+use crate::{
+    describe_command, find_command, registry, CancellationToken, Cancelled, Command, CommandContext,
+};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The command ran, but failed on its input
+pub const COMMAND_FAILED: i64 = -32000;
+pub const REQUEST_CANCELLED: i64 = -32800;
+
+const CANCEL_METHOD: &str = "$/cancelRequest";
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn response(id: &Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    }
+}
+
+/// A request, or a notification if it has no id
+struct Request {
+    id: Option<Value>,
+    method: String,
+    params: Map<String, Value>,
+}
+
+fn parse_request(request: Value) -> Result<Request, (Option<Value>, RpcError)> {
+    let Value::Object(mut request) = request else {
+        return Err((
+            None,
+            RpcError::new(INVALID_REQUEST, "expected a request object"),
+        ));
+    };
+    let id = request.remove("id");
+    if !matches!(
+        id,
+        None | Some(Value::Null) | Some(Value::String(_)) | Some(Value::Number(_))
+    ) {
+        return Err((
+            None,
+            RpcError::new(INVALID_REQUEST, "id must be a string, a number or null"),
+        ));
+    }
+    if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err((
+            id,
+            RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+        ));
+    }
+    let Some(Value::String(method)) = request.remove("method") else {
+        return Err((
+            id,
+            RpcError::new(INVALID_REQUEST, "method must be a string"),
+        ));
+    };
+    let params = match request.remove("params") {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(params)) => params,
+        Some(_) => {
+            return Err((
+                id,
+                RpcError::new(INVALID_PARAMS, "params must be an object"),
+            ))
+        }
+    };
+    Ok(Request { id, method, params })
+}
+
+fn describe(params: &Map<String, Value>) -> Result<Value, RpcError> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "expected the name of a command"))?;
+    find_command(name)
+        .map(describe_command)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("no command named {}", name)))
+}
+
+fn run_command(
+    command: &'static dyn Command,
+    mut params: Map<String, Value>,
+    cancellation: &CancellationToken,
+) -> Result<Value, RpcError> {
+    let input = params.remove("input");
+    let (invocation, datasets) = crate::parse_json_request(command, params, input)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+    let context = CommandContext {
+        datasets: &datasets,
+        cancellation: cancellation.clone(),
+        ..CommandContext::default()
+    };
+    // a panicking command must not take the whole session down with it
+    let (result, allocations) = allocations::track(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            crate::execute_command(&invocation, &context)
+        }))
+    });
+    match (result, allocations) {
+        (Ok(Ok(result)), None) => Ok(result),
+        (Ok(Ok(result)), Some(allocations)) => Ok(json!({
+            "command": command.name(),
+            "result": result,
+            "allocations": allocations,
+        })),
+        (Ok(Err(e)), _) if Cancelled::caused(e.as_ref()) => {
+            Err(RpcError::new(REQUEST_CANCELLED, "request cancelled"))
+        }
+        (Ok(Err(e)), _) => Err(RpcError::new(COMMAND_FAILED, e)),
+        (Err(_), _) => Err(RpcError::new(
+            INTERNAL_ERROR,
+            format!("{} failed unexpectedly", command.name()),
+        )),
+    }
+}
+
+/// The running requests by id, to cancel them
+type Running = Mutex<HashMap<String, CancellationToken>>;
+
+fn write_line(output: &Mutex<impl Write>, message: &Value) {
+    let mut output = output.lock().unwrap();
+    if let Err(e) = writeln!(output, "{}", message).and_then(|_| output.flush()) {
+        error!("Failed to write the response: {}", e);
+    }
+}
+
+/**
+ * Serves the requests read from `input` until it ends, writing the responses to `output`. Returns
+ * once every running request has been answered.
+ */
+pub fn run_rpc(input: impl BufRead, output: impl Write + Send) -> Result<(), Error> {
+    let output = Mutex::new(output);
+    let running: Running = Mutex::new(HashMap::new());
+
+    thread::scope(|scope| {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    write_line(
+                        &output,
+                        &response(&Value::Null, Err(RpcError::new(PARSE_ERROR, e))),
+                    );
+                    continue;
+                }
+            };
+            let Request { id, method, params } = match parse_request(request) {
+                Ok(request) => request,
+                Err((id, e)) => {
+                    write_line(&output, &response(&id.unwrap_or(Value::Null), Err(e)));
+                    continue;
+                }
+            };
+            debug!("{} {:?}", method, id);
+
+            let result = match method.as_str() {
+                "listCommands" => Ok(Value::Array(
+                    registry().into_iter().map(describe_command).collect(),
+                )),
+                "describeCommand" => describe(&params),
+                CANCEL_METHOD => {
+                    if let Some(token) = params
+                        .get("id")
+                        .and_then(|id| running.lock().unwrap().get(&id.to_string()).cloned())
+                    {
+                        token.cancel();
+                    }
+                    Ok(Value::Null)
+                }
+                name => match find_command(name) {
+                    Some(command) => {
+                        let cancellation = CancellationToken::new();
+                        let key = id.as_ref().map(Value::to_string);
+                        // registered before reading on, so a cancel on the next line finds it
+                        if let Some(key) = &key {
+                            running
+                                .lock()
+                                .unwrap()
+                                .insert(key.clone(), cancellation.clone());
+                        }
+                        let (output, running) = (&output, &running);
+                        scope.spawn(move || {
+                            let result = run_command(command, params, &cancellation);
+                            if let Some(key) = key {
+                                running.lock().unwrap().remove(&key);
+                            }
+                            if let Some(id) = id {
+                                write_line(output, &response(&id, result));
+                            }
+                        });
+                        continue;
+                    }
+                    None => Err(RpcError::new(
+                        METHOD_NOT_FOUND,
+                        format!("no method named {}", name),
+                    )),
+                },
+            };
+            if let Some(id) = id {
+                write_line(&output, &response(&id, result));
+            }
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,41 @@
+/*
+ * JSON Schemas derived from the serde types of the inputs and results, and validation against
+ * them.
+ *
+ * The schemas are derived with `schemars`, which follows the serde attributes: a field with a
+ * default is optional. Structs end up in `$defs`, named after the Rust type.
+ */
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+/// The JSON Schema of the values `T` deserializes from, as a standalone document
+pub fn schema_for<T: JsonSchema>(title: &str) -> Value {
+    let mut schema = schemars::schema_for!(T);
+    // the title is the Rust type, named after the command instead
+    schema.remove("title");
+    let mut document = Map::new();
+    if let Some(dialect) = schema.remove("$schema") {
+        document.insert("$schema".to_string(), dialect);
+    }
+    document.insert("title".to_string(), json!(title));
+    if let Value::Object(schema) = schema.to_value() {
+        document.extend(schema);
+    }
+    Value::Object(document)
+}
+
+/**
+ * Validates a value against a schema, returning every error along with the JSON pointer of the
+ * value it is about, e.g. `/items/1/end: "x" is not of type "integer"`. Empty if the value is
+ * valid.
+ */
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => return vec![format!("invalid schema: {}", e)],
+    };
+    validator
+        .iter_errors(value)
+        .map(|error| format!("{}: {}", error.instance_path, error))
+        .collect()
+}
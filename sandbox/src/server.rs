@@ -0,0 +1,229 @@
+/*
+ * HTTP server exposing every command as a JSON endpoint.
+ *
+ * GET  /health        liveness check
+ * GET  /v1/commands   the commands and their arguments
+ * POST /v1/<command>  runs a command
+ *
+ * Commands that read a JSON file take the same JSON as the request body, with any other arguments
+ * in the query string, e.g. `POST /v1/job-sequencing?mode=profit`. Commands without an input file
+ * take their arguments as a JSON object keyed by argument id, e.g.
+ * `POST /v1/rotating-list` with `{"numbers": [1, 2, 3], "k": 1}`.
+ *
+ * Results come back as `{"command": ..., "result": ...}` and failures as `{"error": ...}`.
+ */
+use log::{error, info};
+use serde_json::{json, Map, Value};
+use std::{
+    io::Error,
+    net::{SocketAddr, ToSocketAddrs},
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+    time::Instant,
+};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::allocations;
+use crate::{describe_command, find_command, registry, Command, CommandContext};
+
+pub struct Server {
+    http: Arc<tiny_http::Server>,
+}
+
+/// A response to send back, with its status code
+struct Reply {
+    status: u16,
+    body: Value,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Reply { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        Reply {
+            status,
+            body: json!({ "error": message.to_string() }),
+        }
+    }
+}
+
+/// The shared description, along with the endpoint to call
+fn describe_endpoint(command: &dyn Command) -> Value {
+    let mut description = describe_command(command);
+    description["endpoint"] = json!(format!("/v1/{}", command.name()));
+    description
+}
+
+/// Decodes `%XX` escapes and `+` in a query string component
+fn decode_query_component(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_query(query: &str) -> Map<String, Value> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (
+                decode_query_component(key),
+                Value::String(decode_query_component(value)),
+            )
+        })
+        .collect()
+}
+
+/// Runs a command on the arguments and the request body
+fn run(command: &'static dyn Command, query: &str, body: &str) -> Reply {
+    let mut args = parse_query(query);
+    if args.contains_key("json_file_path") {
+        return Reply::error(
+            400,
+            "the input goes in the request body, not in json_file_path",
+        );
+    }
+
+    let body = body.trim();
+    let mut input = None;
+    if !body.is_empty() {
+        let value: Value = match serde_json::from_str(body) {
+            Ok(value) => value,
+            Err(e) => return Reply::error(400, format!("Invalid JSON body: {}", e)),
+        };
+        if command.takes_input() {
+            input = Some(value);
+        } else {
+            match value {
+                Value::Object(body_args) => args.extend(body_args),
+                _ => return Reply::error(400, "expected a JSON object of arguments"),
+            }
+        }
+    }
+
+    let (invocation, datasets) = match crate::parse_json_request(command, args, input) {
+        Ok(parsed) => parsed,
+        Err(e) => return Reply::error(400, e),
+    };
+    let context = CommandContext {
+        datasets: &datasets,
+        ..CommandContext::default()
+    };
+    // a panicking command must not take the worker down with it
+    let (result, allocations) = allocations::track(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            crate::execute_command(&invocation, &context).map_err(|e| e.to_string())
+        }))
+    });
+    match (result, allocations) {
+        (Ok(Ok(result)), None) => Reply::ok(json!({ "command": command.name(), "result": result })),
+        // with the track-allocations feature, the heap usage of the run goes along with its result
+        (Ok(Ok(result)), Some(allocations)) => Reply::ok(json!({
+            "command": command.name(),
+            "result": result,
+            "allocations": allocations,
+        })),
+        (Ok(Err(e)), _) => Reply::error(422, e),
+        (Err(_), _) => Reply::error(500, format!("{} failed unexpectedly", command.name())),
+    }
+}
+
+fn route(request: &mut Request) -> Reply {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let method = request.method().clone();
+
+    match (method, path) {
+        (Method::Get, "/health") => {
+            Reply::ok(json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }))
+        }
+        (Method::Get, "/v1/commands") => Reply::ok(Value::Array(
+            registry().into_iter().map(describe_endpoint).collect(),
+        )),
+        (_, "/health") | (_, "/v1/commands") => Reply::error(405, "method not allowed"),
+        (method, path) => {
+            let Some(command) = path.strip_prefix("/v1/").and_then(find_command) else {
+                return Reply::error(404, format!("no such endpoint: {}", path));
+            };
+            if method != Method::Post {
+                return Reply::error(405, "method not allowed, use POST");
+            }
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().read_to_string(&mut body) {
+                return Reply::error(400, format!("Cannot read the request body: {}", e));
+            }
+            run(command, query, &body)
+        }
+    }
+}
+
+fn handle(mut request: Request) {
+    let started = Instant::now();
+    let reply = route(&mut request);
+    info!(
+        "{} {} {} in {:?}",
+        request.method(),
+        request.url(),
+        reply.status,
+        started.elapsed()
+    );
+    let response = Response::from_string(reply.body.to_string())
+        .with_status_code(reply.status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+    if let Err(e) = request.respond(response) {
+        error!("Failed to respond: {}", e);
+    }
+}
+
+impl Server {
+    /// Binds the server, port 0 picks a free port
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        let http = tiny_http::Server::http(addr).map_err(Error::other)?;
+        Ok(Server {
+            http: Arc::new(http),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.http.server_addr().to_ip().unwrap()
+    }
+
+    /// Serves requests on the given number of worker threads, until the process ends
+    pub fn run(&self, workers: usize) {
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|_| {
+                let http = Arc::clone(&self.http);
+                thread::spawn(move || {
+                    for request in http.incoming_requests() {
+                        handle(request);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            let _ = handle.join();
+        }
+    }
+}
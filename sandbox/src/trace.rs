@@ -0,0 +1,486 @@
+/*
+ * Structured events the algorithms emit while they run, to record them with `--trace <file>` and
+ * step through them again with `replay <file>`.
+ *
+ * Every traced algorithm starts with an event carrying its input, so a replay can render the
+ * state from the events alone.
+ */
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    fmt,
+    io::{Error, ErrorKind},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TracedTask {
+    pub title: String,
+    pub start: i32,
+    pub end: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// daily-temperature: the temperatures to scan and the mode
+    TemperatureScan {
+        temperatures: Vec<i32>,
+        mode: String,
+    },
+    /// daily-temperature: a day waits on the stack for a warmer one
+    StackPush { day: usize },
+    /// daily-temperature: a day leaves the stack, as `warmer_day` is warmer
+    StackPop { day: usize, warmer_day: usize },
+    /// daily-temperature: a day is compared with a later one
+    Compare { day: usize, with: usize },
+    /// daily-temperature: the number of days to wait after `day` is known
+    Answer { day: usize, days: usize },
+
+    /// rotating-list: the list to rotate
+    ListRotation { numbers: Vec<i32>, k: i32 },
+    /// rotating-list: the list is split before the node at `at`, which becomes the new head
+    Split { at: usize },
+    /// rotating-list: the node holding `from` is linked to the node holding `to`
+    Relink { from: i32, to: i32 },
+
+    /// schedule: the tasks to pick from
+    ScheduleTasks { tasks: Vec<TracedTask> },
+    /// schedule: the task ending first is picked
+    Pick { task: TracedTask },
+    /// schedule: a task is dropped as it starts before the picked one ends
+    Reject {
+        task: TracedTask,
+        conflicts_with: String,
+    },
+
+    /// sufficient-coverage-set: a problem to evaluate
+    CoverageProblem {
+        problem: usize,
+        numbers: Vec<u32>,
+        tickets: Vec<Vec<u32>>,
+        min_numbers_to_cover: u32,
+    },
+    /// sufficient-coverage-set: a combination was checked, covered if a ticket covers it
+    ComboCheck {
+        problem: usize,
+        combo: Vec<u32>,
+        ticket: Option<usize>,
+        implied: bool,
+    },
+    /// sufficient-coverage-set: the result of a problem
+    ProblemResult { problem: usize, covered: bool },
+}
+
+fn task_label(task: &TracedTask) -> String {
+    format!("{} [{}, {})", task.title, task.start, task.end)
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::TemperatureScan { temperatures, mode } => {
+                write!(f, "scan {} temperatures using {}", temperatures.len(), mode)
+            }
+            TraceEvent::StackPush { day } => write!(f, "push day {}", day),
+            TraceEvent::StackPop { day, warmer_day } => {
+                write!(f, "pop day {}, day {} is warmer", day, warmer_day)
+            }
+            TraceEvent::Compare { day, with } => write!(f, "compare day {} with day {}", day, with),
+            TraceEvent::Answer { day, days } => write!(f, "day {} waits {} days", day, days),
+            TraceEvent::ListRotation { numbers, k } => {
+                write!(f, "rotate {} numbers by {}", numbers.len(), k)
+            }
+            TraceEvent::Split { at } => write!(f, "split before node {}", at),
+            TraceEvent::Relink { from, to } => write!(f, "link {} -> {}", from, to),
+            TraceEvent::ScheduleTasks { tasks } => write!(f, "schedule {} tasks", tasks.len()),
+            TraceEvent::Pick { task } => write!(f, "pick {}", task_label(task)),
+            TraceEvent::Reject {
+                task,
+                conflicts_with,
+            } => write!(
+                f,
+                "reject {}, overlaps {}",
+                task_label(task),
+                conflicts_with
+            ),
+            TraceEvent::CoverageProblem { problem, .. } => {
+                write!(f, "evaluate problem {}", problem)
+            }
+            TraceEvent::ComboCheck {
+                combo,
+                ticket: Some(ticket),
+                implied,
+                ..
+            } => write!(
+                f,
+                "combo {:?} {} by ticket {}",
+                combo,
+                if *implied { "implied" } else { "covered" },
+                ticket
+            ),
+            TraceEvent::ComboCheck {
+                combo,
+                ticket: None,
+                ..
+            } => write!(f, "combo {:?} is not covered", combo),
+            TraceEvent::ProblemResult { problem, covered } => write!(
+                f,
+                "problem {} is {}",
+                problem,
+                if *covered { "covered" } else { "not covered" }
+            ),
+        }
+    }
+}
+
+/// Receives the events of an algorithm as it runs
+pub trait Observer {
+    fn on_event(&self, event: TraceEvent);
+}
+
+/**
+ * Where an algorithm sends its events. The events are only built when someone observes them, so
+ * tracing costs nothing when it is off.
+ */
+#[derive(Clone, Copy)]
+pub struct Tracer<'a> {
+    observer: Option<&'a dyn Observer>,
+}
+
+impl<'a> Tracer<'a> {
+    /// A tracer that drops every event
+    pub fn none() -> Self {
+        Tracer { observer: None }
+    }
+
+    pub fn new(observer: &'a dyn Observer) -> Self {
+        Tracer {
+            observer: Some(observer),
+        }
+    }
+
+    pub fn emit(&self, event: impl FnOnce() -> TraceEvent) {
+        if let Some(observer) = self.observer {
+            observer.on_event(event());
+        }
+    }
+}
+
+/// Keeps every event, to write them to a trace file
+#[derive(Default)]
+pub struct TraceRecorder {
+    events: RefCell<Vec<TraceEvent>>,
+}
+
+impl TraceRecorder {
+    pub fn into_trace(self, command: &str) -> Trace {
+        Trace {
+            command: command.to_string(),
+            events: self.events.into_inner(),
+        }
+    }
+}
+
+impl Observer for TraceRecorder {
+    fn on_event(&self, event: TraceEvent) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+/// The content of a trace file
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Trace {
+    pub command: String,
+    pub events: Vec<TraceEvent>,
+}
+
+/// The state of the traced algorithm, rebuilt from its events
+#[derive(Default)]
+pub enum ReplayState {
+    #[default]
+    Empty,
+    Temperatures {
+        temperatures: Vec<i32>,
+        stack: Vec<usize>,
+        answers: Vec<Option<usize>>,
+        comparing: Option<(usize, usize)>,
+    },
+    Rotation {
+        numbers: Vec<i32>,
+        split: Option<usize>,
+    },
+    Schedule {
+        remaining: Vec<TracedTask>,
+        picked: Vec<TracedTask>,
+        rejected: Vec<(TracedTask, String)>,
+    },
+    Coverage {
+        problem: usize,
+        numbers: Vec<u32>,
+        tickets: Vec<Vec<u32>>,
+        min_numbers_to_cover: u32,
+        checked: usize,
+        covered: usize,
+        result: Option<bool>,
+    },
+}
+
+/// An event that does not fit the state the events before it built, as in a truncated or edited trace
+fn misplaced(event: &TraceEvent) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("{} does not fit the events before it", event),
+    )
+}
+
+impl ReplayState {
+    /// Advances the state by one event, failing on an event that refers to days or nodes the
+    /// traced input does not have
+    pub fn apply(&mut self, event: &TraceEvent) -> Result<(), Error> {
+        match (&mut *self, event) {
+            (_, TraceEvent::TemperatureScan { temperatures, .. }) => {
+                *self = ReplayState::Temperatures {
+                    temperatures: temperatures.clone(),
+                    stack: vec![],
+                    answers: vec![None; temperatures.len()],
+                    comparing: None,
+                }
+            }
+            (
+                ReplayState::Temperatures {
+                    temperatures,
+                    stack,
+                    ..
+                },
+                TraceEvent::StackPush { day },
+            ) => {
+                if *day >= temperatures.len() {
+                    return Err(misplaced(event));
+                }
+                stack.push(*day)
+            }
+            (
+                ReplayState::Temperatures { stack, answers, .. },
+                TraceEvent::StackPop { day, warmer_day },
+            ) => {
+                let wait = warmer_day
+                    .checked_sub(*day)
+                    .ok_or_else(|| misplaced(event))?;
+                *answers.get_mut(*day).ok_or_else(|| misplaced(event))? = Some(wait);
+                stack.retain(|d| d != day);
+            }
+            (ReplayState::Temperatures { comparing, .. }, TraceEvent::Compare { day, with }) => {
+                *comparing = Some((*day, *with))
+            }
+            (
+                ReplayState::Temperatures {
+                    answers, comparing, ..
+                },
+                TraceEvent::Answer { day, days },
+            ) => {
+                *answers.get_mut(*day).ok_or_else(|| misplaced(event))? = Some(*days);
+                *comparing = None;
+            }
+            (_, TraceEvent::ListRotation { numbers, .. }) => {
+                *self = ReplayState::Rotation {
+                    numbers: numbers.clone(),
+                    split: None,
+                }
+            }
+            (ReplayState::Rotation { numbers, split }, TraceEvent::Split { at }) => {
+                if *at > numbers.len() {
+                    return Err(misplaced(event));
+                }
+                *split = Some(*at)
+            }
+            (ReplayState::Rotation { numbers, split }, TraceEvent::Relink { .. }) => {
+                if let Some(at) = split.take() {
+                    numbers.rotate_left(at);
+                }
+            }
+            (_, TraceEvent::ScheduleTasks { tasks }) => {
+                *self = ReplayState::Schedule {
+                    remaining: tasks.clone(),
+                    picked: vec![],
+                    rejected: vec![],
+                }
+            }
+            (
+                ReplayState::Schedule {
+                    remaining, picked, ..
+                },
+                TraceEvent::Pick { task },
+            ) => {
+                remaining.retain(|t| t != task);
+                picked.push(task.clone());
+            }
+            (
+                ReplayState::Schedule {
+                    remaining,
+                    rejected,
+                    ..
+                },
+                TraceEvent::Reject {
+                    task,
+                    conflicts_with,
+                },
+            ) => {
+                remaining.retain(|t| t != task);
+                rejected.push((task.clone(), conflicts_with.clone()));
+            }
+            (
+                _,
+                TraceEvent::CoverageProblem {
+                    problem,
+                    numbers,
+                    tickets,
+                    min_numbers_to_cover,
+                },
+            ) => {
+                *self = ReplayState::Coverage {
+                    problem: *problem,
+                    numbers: numbers.clone(),
+                    tickets: tickets.clone(),
+                    min_numbers_to_cover: *min_numbers_to_cover,
+                    checked: 0,
+                    covered: 0,
+                    result: None,
+                }
+            }
+            (
+                ReplayState::Coverage {
+                    checked, covered, ..
+                },
+                TraceEvent::ComboCheck { ticket, .. },
+            ) => {
+                *checked += 1;
+                if ticket.is_some() {
+                    *covered += 1;
+                }
+            }
+            (ReplayState::Coverage { result, .. }, TraceEvent::ProblemResult { covered, .. }) => {
+                *result = Some(*covered)
+            }
+            // an event without the event that starts its algorithm, nothing to render
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Joins the values into a row of cells of the same width
+fn row(label: &str, cells: impl Iterator<Item = String>) -> String {
+    let cells: Vec<String> = cells.map(|cell| format!("{:>5}", cell)).collect();
+    format!("{:<12}{}", label, cells.join(""))
+}
+
+impl fmt::Display for ReplayState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayState::Empty => writeln!(f, "(nothing traced yet)"),
+            ReplayState::Temperatures {
+                temperatures,
+                stack,
+                answers,
+                comparing,
+            } => {
+                writeln!(
+                    f,
+                    "{}",
+                    row("day", (0..temperatures.len()).map(|d| d.to_string()))
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    row("temperature", temperatures.iter().map(|t| t.to_string()))
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    row(
+                        "wait",
+                        answers
+                            .iter()
+                            .map(|a| a.map_or("-".to_string(), |a| a.to_string()))
+                    )
+                )?;
+                if let Some((day, with)) = comparing {
+                    writeln!(f, "comparing day {} with day {}", day, with)?;
+                }
+                writeln!(
+                    f,
+                    "stack: [{}]",
+                    stack
+                        .iter()
+                        .map(|d| format!("{} ({})", d, temperatures[*d]))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
+            ReplayState::Rotation { numbers, split } => {
+                let nodes: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+                match split {
+                    Some(at) => writeln!(
+                        f,
+                        "{} | {}",
+                        nodes[..*at].join(" -> "),
+                        nodes[*at..].join(" -> ")
+                    ),
+                    None => writeln!(f, "{}", nodes.join(" -> ")),
+                }
+            }
+            ReplayState::Schedule {
+                remaining,
+                picked,
+                rejected,
+            } => {
+                let labels = |tasks: &[TracedTask]| {
+                    tasks
+                        .iter()
+                        .map(task_label)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                };
+                writeln!(f, "picked:    {}", labels(picked))?;
+                writeln!(
+                    f,
+                    "rejected:  {}",
+                    rejected
+                        .iter()
+                        .map(|(task, conflict)| format!(
+                            "{} (overlaps {})",
+                            task_label(task),
+                            conflict
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )?;
+                writeln!(f, "remaining: {}", labels(remaining))
+            }
+            ReplayState::Coverage {
+                problem,
+                numbers,
+                tickets,
+                min_numbers_to_cover,
+                checked,
+                covered,
+                result,
+            } => {
+                writeln!(
+                    f,
+                    "problem {}: cover {} of {:?}",
+                    problem, min_numbers_to_cover, numbers
+                )?;
+                for (i, ticket) in tickets.iter().enumerate() {
+                    writeln!(f, "  ticket {}: {:?}", i, ticket)?;
+                }
+                writeln!(f, "combos checked: {}, covered: {}", checked, covered)?;
+                match result {
+                    Some(true) => writeln!(f, "result: covered"),
+                    Some(false) => writeln!(f, "result: not covered"),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
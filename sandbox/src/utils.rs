@@ -1,15 +1,185 @@
+use serde_json::Value;
 use std::{
-    fs::File,
-    io::{BufReader, Error, ErrorKind},
-    path::PathBuf,
+    cell::Cell,
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
 }; // path buffer, to construct paths
 
-pub fn parse_json_file<T: serde::de::DeserializeOwned>(path: &PathBuf) -> Result<T, Error> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
+use crate::input::{self, InputFormat};
 
-    let schedule =
-        serde_json::from_reader(reader).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+thread_local! {
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+/**
+ * Runs `f` with only warnings and errors logged, for algorithms whose logging would be noise. Only
+ * the logs of the current thread are quieted, other threads keep logging as they were.
+ */
+pub fn quietly<T>(f: impl FnOnce() -> T) -> T {
+    let quiet = QUIET.replace(true);
+    let result = f();
+    QUIET.set(quiet);
+    result
+}
+
+/// Whether the current thread is running `quietly`, checked by the logger
+pub fn is_quiet() -> bool {
+    QUIET.get()
+}
+
+/// XDG base directory from the environment variable, falling back to a directory under $HOME
+pub fn xdg_dir(var: &str, home_fallback: &str) -> Option<PathBuf> {
+    match std::env::var_os(var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)),
+    }
+}
+
+/// JSON documents loaded ahead of time, which can be referenced as `@name` instead of a file path
+pub type Datasets = HashMap<String, serde_json::Value>;
+
+/// Returns the dataset name if the path is a reference in the form of `@name`
+pub fn dataset_name(path: &Path) -> Option<&str> {
+    path.to_str().and_then(|p| p.strip_prefix('@'))
+}
+
+/// Parses the input from a `@name` dataset, or from a file in the given format or the one its
+/// extension implies
+pub fn parse_input<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    format: Option<InputFormat>,
+    datasets: &Datasets,
+) -> Result<T, Error> {
+    match dataset_name(path) {
+        Some(name) => {
+            let value = datasets.get(name).ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("Unknown dataset: {}", name))
+            })?;
+            serde_json::from_value(value.clone()).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        }
+        None => input::parse_input_file(path, format),
+    }
+}
+
+/**
+ * Differences between the expected and actual JSON, one line per differing path, e.g.
+ * `$.items[0].end: expected 3, got 4`. Empty if they are equal.
+ */
+pub fn diff_json(expected: &Value, actual: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    collect_json_differences("$", expected, actual, &mut differences);
+    differences
+}
+
+/// Beyond this many line pairs, `diff_lines` replaces everything instead of finding what changed
+const MAX_DIFF_CELLS: usize = 10_000_000;
+
+/**
+ * Line differences between two texts, as hunks of removed (`- `) and added (`+ `) lines headed by
+ * the line numbers they start at, e.g. `@@ -3 +3 @@`. Empty if the texts are equal.
+ */
+pub fn diff_lines(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if old == new {
+        return vec![];
+    }
+
+    // longest common subsequence of lines, from the end so the walk below goes forward. Without
+    // it, the walk removes the rest of the old lines before adding the new ones.
+    let (n, m) = (old.len(), new.len());
+    let common = (n.saturating_mul(m) <= MAX_DIFF_CELLS).then(|| {
+        let mut common = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                common[i][j] = if old[i] == new[j] {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+        common
+    });
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        lines.push(format!("@@ -{} +{} @@", i + 1, j + 1));
+        while i < n || j < m {
+            if i < n && j < m && old[i] == new[j] {
+                break;
+            }
+            let old_first = |common: &Vec<Vec<usize>>| common[i + 1][j] >= common[i][j + 1];
+            if j == m || (i < n && common.as_ref().is_none_or(old_first)) {
+                lines.push(format!("- {}", old[i]));
+                i += 1;
+            } else {
+                lines.push(format!("+ {}", new[j]));
+                j += 1;
+            }
+        }
+    }
+    lines
+}
 
-    Ok(schedule)
+fn collect_json_differences(
+    path: &str,
+    expected: &Value,
+    actual: &Value,
+    differences: &mut Vec<String>,
+) {
+    match (expected, actual) {
+        (Value::Object(expected_map), Value::Object(actual_map)) => {
+            for (key, expected_value) in expected_map {
+                let key_path = format!("{}.{}", path, key);
+                match actual_map.get(key) {
+                    Some(actual_value) => collect_json_differences(
+                        &key_path,
+                        expected_value,
+                        actual_value,
+                        differences,
+                    ),
+                    None => differences.push(format!("{}: missing", key_path)),
+                }
+            }
+            for key in actual_map
+                .keys()
+                .filter(|key| !expected_map.contains_key(*key))
+            {
+                differences.push(format!("{}.{}: unexpected", path, key));
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            for (i, (expected_item, actual_item)) in
+                expected_items.iter().zip(actual_items).enumerate()
+            {
+                collect_json_differences(
+                    &format!("{}[{}]", path, i),
+                    expected_item,
+                    actual_item,
+                    differences,
+                );
+            }
+            if expected_items.len() != actual_items.len() {
+                differences.push(format!(
+                    "{}: expected {} items, got {}",
+                    path,
+                    expected_items.len(),
+                    actual_items.len()
+                ));
+            }
+        }
+        _ => {
+            if expected != actual {
+                differences.push(format!("{}: expected {}, got {}", path, expected, actual));
+            }
+        }
+    }
 }
@@ -0,0 +1,132 @@
+/*
+ * Watch mode: runs a file based command, then runs it again whenever its input file changes,
+ * printing how the output differs from the previous run.
+ *
+ * The file is polled, which works the same on every platform and for every editor, whether it
+ * writes the file in place or replaces it. A change only triggers a run once the file has stopped
+ * changing for a moment, so a save written in several steps runs the command once.
+ */
+use std::{
+    error::Error,
+    fs,
+    io::{self, ErrorKind, Write},
+    path::Path,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    execute_command, input, utils, CancellationToken, CommandContext, Datasets, Invocation,
+};
+
+/// How often the input file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// How long the input file has to stay the same before the command runs again
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// What tells the versions of a file apart, None while it does not exist
+fn file_signature(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// The output as it is printed, charts as they are and anything else as pretty JSON
+fn format_output(result: &serde_json::Value) -> String {
+    match result {
+        serde_json::Value::String(text) => text.trim_end().to_string(),
+        _ => serde_json::to_string_pretty(result).unwrap_or_default(),
+    }
+}
+
+/**
+ * Runs the command and runs it again on every change of its input file until `stop` is cancelled,
+ * writing the first output and then the differences to `output`. Errors, like an input file that
+ * does not parse while it is being edited, are written as well and the watch goes on.
+ */
+pub fn watch_command(
+    invocation: &Invocation,
+    output: &mut impl Write,
+    stop: &CancellationToken,
+) -> Result<(), Box<dyn Error>> {
+    let path = match invocation.json_file_path() {
+        Some(path) if utils::dataset_name(path).is_none() && !input::is_stdin(path) => path,
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("--watch needs {} to read an input file", invocation.name()),
+            )
+            .into())
+        }
+    };
+    let datasets = Datasets::new();
+    let cancellation = CancellationToken::new();
+    let context = CommandContext {
+        datasets: &datasets,
+        cancellation: cancellation.clone(),
+        ..CommandContext::default()
+    };
+
+    writeln!(
+        output,
+        "watching {} for changes to re-run {}",
+        path.display(),
+        invocation.name()
+    )?;
+    let mut signature = file_signature(path);
+    // the output of the last successful run, to compare the next one with
+    let mut previous: Option<String> = None;
+    loop {
+        match execute_command(invocation, &context) {
+            Ok(result) => {
+                let current = format_output(&result);
+                match &previous {
+                    None => writeln!(output, "{}", current)?,
+                    Some(previous) => {
+                        let differences = utils::diff_lines(previous, &current);
+                        if differences.is_empty() {
+                            writeln!(output, "(the output did not change)")?;
+                        }
+                        for line in differences {
+                            writeln!(output, "{}", line)?;
+                        }
+                    }
+                }
+                previous = Some(current);
+            }
+            Err(e) => writeln!(output, "error: {}", e)?,
+        }
+        output.flush()?;
+
+        // wait for a change, and then for the file to settle
+        loop {
+            if stop.is_cancelled() {
+                return Ok(());
+            }
+            thread::sleep(POLL_INTERVAL);
+            let current = file_signature(path);
+            if current == signature {
+                continue;
+            }
+            signature = current;
+            loop {
+                thread::sleep(DEBOUNCE);
+                let settled = file_signature(path);
+                if settled == signature {
+                    break;
+                }
+                signature = settled;
+            }
+            if signature.is_some() {
+                break;
+            }
+            writeln!(output, "-- {} was removed, waiting for it", path.display())?;
+            output.flush()?;
+        }
+        writeln!(
+            output,
+            "-- {} changed, re-running {}",
+            path.display(),
+            invocation.name()
+        )?;
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * Calls the C API of dat_cli and checks the results, exiting with a non-zero status on failure.
+ * Built and run by tests/ffi.rs, or by hand after `cargo build`:
+ *
+ *   cc test/c/ffi_test.c -Iinclude -Ltarget/debug -ldat_cli -Wl,-rpath,target/debug -o ffi_test
+ *   ./ffi_test
+ *
+ * or against the static library:
+ *
+ *   cc test/c/ffi_test.c -Iinclude target/debug/libdat_cli.a -lpthread -ldl -lm -o ffi_test
+ */
+#include <stdio.h>
+#include <string.h>
+
+#include "dat_cli.h"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                   \
+    do {                                                                   \
+        if (!(condition)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #condition);                                           \
+            failures++;                                                    \
+        }                                                                  \
+    } while (0)
+
+static void test_daily_temperature(void) {
+    const int32_t temperatures[] = {73, 74, 75, 71, 69, 72, 76, 73};
+    const size_t expected[] = {1, 1, 4, 2, 1, 1, 0, 0};
+    const size_t len = sizeof(temperatures) / sizeof(temperatures[0]);
+    const DatCliDailyTemperatureMode modes[] = {DAT_CLI_DAILY_TEMPERATURE_MODE_STACK,
+                                                DAT_CLI_DAILY_TEMPERATURE_MODE_REVERSE};
+
+    for (size_t m = 0; m < 2; m++) {
+        size_t days[8] = {0};
+        char *error = NULL;
+        CHECK(dat_cli_daily_temperature(temperatures, len, modes[m], days, &error) ==
+              DAT_CLI_STATUS_OK);
+        CHECK(error == NULL);
+        CHECK(memcmp(days, expected, sizeof(expected)) == 0);
+    }
+
+    /* a mode that is not one of DatCliDailyTemperatureMode is rejected */
+    size_t days[8] = {0};
+    char *error = NULL;
+    CHECK(dat_cli_daily_temperature(temperatures, len, 7, days, &error) ==
+          DAT_CLI_STATUS_INVALID_ARGUMENT);
+    CHECK(error != NULL && strstr(error, "7") != NULL);
+    dat_cli_free_error(error);
+}
+
+static void test_rotate_right(void) {
+    const int32_t numbers[] = {1, 2, 3, 4, 5};
+    const int32_t expected[] = {4, 5, 1, 2, 3};
+    int32_t rotated[5] = {0};
+    CHECK(dat_cli_rotate_right(numbers, 5, 2, rotated, NULL) == DAT_CLI_STATUS_OK);
+    CHECK(memcmp(rotated, expected, sizeof(expected)) == 0);
+
+    /* nothing to rotate, so no buffers are needed */
+    CHECK(dat_cli_rotate_right(NULL, 0, 3, NULL, NULL) == DAT_CLI_STATUS_OK);
+}
+
+static void test_schedule(void) {
+    const DatCliTask tasks[] = {{1, 3}, {2, 4}, {3, 5}, {0, 7}, {5, 6}};
+    const size_t expected[] = {0, 2, 4};
+    size_t picked[5] = {0};
+    size_t picked_len = 0;
+    CHECK(dat_cli_schedule(tasks, 5, picked, &picked_len, NULL) == DAT_CLI_STATUS_OK);
+    CHECK(picked_len == 3);
+    CHECK(memcmp(picked, expected, sizeof(expected)) == 0);
+}
+
+static void test_run(void) {
+    const char *args[] = {"rotating-list", "-n", "1,2,3,4,5", "-k", "2"};
+    char *result = NULL;
+    CHECK(dat_cli_run(args, 5, &result, NULL) == DAT_CLI_STATUS_OK);
+    CHECK(result != NULL && strcmp(result, "[4,5,1,2,3]") == 0);
+    dat_cli_free_result(result);
+
+    const char *unknown[] = {"no-such-command"};
+    char *error = NULL;
+    CHECK(dat_cli_run(unknown, 1, &result, &error) == DAT_CLI_STATUS_INVALID_ARGUMENT);
+    CHECK(error != NULL && strstr(error, "no-such-command") != NULL);
+    dat_cli_free_error(error);
+}
+
+static void test_errors(void) {
+    int32_t rotated[3] = {0};
+    const int32_t numbers[] = {1, 2, 3};
+    char *error = NULL;
+
+    CHECK(dat_cli_rotate_right(NULL, 3, 1, rotated, &error) == DAT_CLI_STATUS_NULL_POINTER);
+    CHECK(error != NULL && strstr(error, "numbers") != NULL);
+    dat_cli_free_error(error);
+
+    error = NULL;
+    CHECK(dat_cli_rotate_right(numbers, 3, -1, rotated, &error) ==
+          DAT_CLI_STATUS_INVALID_ARGUMENT);
+    CHECK(error != NULL);
+    dat_cli_free_error(error);
+
+    const DatCliTask backwards[] = {{5, 1}};
+    size_t picked[1];
+    size_t picked_len;
+    CHECK(dat_cli_schedule(backwards, 1, picked, &picked_len, NULL) ==
+          DAT_CLI_STATUS_INVALID_ARGUMENT);
+
+    /* freeing nothing is fine */
+    dat_cli_free_error(NULL);
+}
+
+int main(void) {
+    test_daily_temperature();
+    test_rotate_right();
+    test_schedule();
+    test_run();
+    test_errors();
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("all checks passed\n");
+    return 0;
+}
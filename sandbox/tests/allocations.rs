@@ -0,0 +1,73 @@
+/*
+ * Heap usage of the runs: tracked with the track-allocations feature, and nothing without it.
+ * `cargo test --features track-allocations` runs the tracked side.
+ */
+use dat_cli::{format_bytes, track_allocations};
+
+#[test]
+fn bytes_are_formatted_in_binary_units() {
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.5 KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+}
+
+#[cfg(not(feature = "track-allocations"))]
+#[test]
+fn nothing_is_tracked_without_the_feature() {
+    let (numbers, stats) = track_allocations(|| vec![0u64; 1000]);
+    assert_eq!(numbers.len(), 1000);
+    assert!(stats.is_none());
+}
+
+#[cfg(feature = "track-allocations")]
+#[test]
+fn tracks_peak_total_and_count() {
+    let (_, stats) = track_allocations(|| {
+        for _ in 0..3 {
+            drop(std::hint::black_box(vec![0u64; 1000]));
+        }
+    });
+    let stats = stats.unwrap();
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.total_bytes, 3 * 8000);
+    assert_eq!(stats.peak_bytes, 8000);
+}
+
+#[cfg(feature = "track-allocations")]
+#[test]
+fn nested_runs_count_for_both() {
+    let (inner, outer) = track_allocations(|| {
+        let kept = std::hint::black_box(vec![0u8; 100]);
+        let (_, inner) = track_allocations(|| std::hint::black_box(vec![0u8; 50]));
+        drop(kept);
+        inner
+    });
+    let (inner, outer) = (inner.unwrap(), outer.unwrap());
+    assert_eq!(inner.total_bytes, 50);
+    assert_eq!(outer.total_bytes, 150);
+    assert_eq!(outer.peak_bytes, 150);
+}
+
+#[cfg(feature = "track-allocations")]
+#[test]
+fn the_cli_and_rpc_results_carry_the_heap_usage() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args(["-q", "rotating-list", "-n", "1,2,3", "-k", "1"])
+        .output()
+        .unwrap();
+    let printed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(printed["result"], serde_json::json!([3, 1, 2]));
+    assert!(printed["allocations"]["count"].as_u64().unwrap() > 0);
+
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "rotating-list",
+        "params": { "numbers": [1, 2, 3], "k": 1 },
+    });
+    let mut output = Vec::new();
+    dat_cli::run_rpc(format!("{}\n", request).as_bytes(), &mut output).unwrap();
+    let response: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(response["result"]["result"], serde_json::json!([3, 1, 2]));
+    assert!(response["result"]["allocations"]["count"].as_u64().unwrap() > 0);
+}
@@ -0,0 +1,97 @@
+/*
+ * Batch manifests: the cases in test/assets/batch.toml all pass, and failing or broken cases are
+ * counted without stopping the run.
+ */
+use dat_cli::run_batch;
+use serde_json::json;
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+fn manifest(name: &str, content: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn the_asset_manifest_passes() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/assets/batch.toml");
+    let summary = run_batch(&path).unwrap();
+    assert!(summary.is_success());
+    assert_eq!(summary.passed, 8);
+}
+
+#[test]
+fn failures_and_errors_are_counted() {
+    let path = manifest(
+        "batch_failures.json",
+        &json!({
+            "datasets": { "tasks": { "items": [{ "title": "a", "start": 0, "end": 1 }] } },
+            "cases": [
+                {
+                    "name": "passes with a dataset",
+                    "command": ["schedule", "--json-file-path", "@tasks"],
+                    "expected": { "items": [{ "title": "a", "start": 0, "end": 1 }] },
+                },
+                {
+                    "name": "passes with its own input",
+                    "command": ["schedule", "--json-file-path", "@input"],
+                    "input": { "items": [] },
+                    "expected": { "items": [] },
+                },
+                {
+                    "name": "gives another result",
+                    "command": ["rotating-list", "--numbers", "1,2,3", "-k", "1"],
+                    "expected": [1, 2, 3],
+                },
+                {
+                    "name": "does not parse",
+                    "command": ["rotating-list", "--no-such-flag"],
+                    "expected": [],
+                },
+                {
+                    "name": "reads a missing file",
+                    "command": ["schedule", "--json-file-path", "missing.json"],
+                    "expected": { "items": [] },
+                },
+            ],
+        })
+        .to_string(),
+    );
+    let summary = run_batch(&path).unwrap();
+    assert!(!summary.is_success());
+    assert_eq!((summary.passed, summary.failed), (2, 3));
+}
+
+#[test]
+fn a_broken_manifest_is_an_error() {
+    let path = manifest("batch_broken.toml", "[[cases]]\nname = \"no command\"\n");
+    let error = run_batch(&path).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+fn run_cli(manifest: &std::path::Path) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .arg("batch")
+        .arg(manifest)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn the_cli_reports_a_missing_manifest() {
+    let output = run_cli(&PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("no_such_batch.toml"));
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error:"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn the_cli_reports_an_unparsable_manifest() {
+    let path = manifest("batch_unparsable.toml", "[[cases]\nname = ");
+    let output = run_cli(&path);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error:"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
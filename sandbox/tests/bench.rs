@@ -0,0 +1,57 @@
+/*
+ * Benchmarks driven through the command registry: every command with a `bench` can be run, on
+ * default sizes of its own when none are given.
+ */
+use dat_cli::{bench_command_names, run_bench};
+
+#[test]
+fn every_command_is_benchmarked_in_each_of_its_modes() {
+    for command in bench_command_names() {
+        let report = run_bench(command, Some(&[10, 20]), 1, 0, false, false).unwrap();
+        assert!(!report.growth.is_empty(), "{}", command);
+        assert_eq!(report.samples.len(), report.growth.len() * 2, "{}", command);
+    }
+}
+
+#[test]
+fn the_default_sizes_suit_the_command() {
+    // the coverage search grows too fast for the sizes the linear commands run on
+    let report = run_bench("sufficient-coverage-set", None, 1, 0, false, false).unwrap();
+    let sizes: Vec<usize> = report.samples.iter().map(|sample| sample.size).collect();
+    assert_eq!(sizes, [20, 40, 80]);
+
+    let report = run_bench("job-sequencing", None, 1, 0, false, false).unwrap();
+    let sizes: Vec<usize> = report.samples.iter().map(|sample| sample.size).collect();
+    // both modes, one after the other
+    assert_eq!(sizes, [250, 500, 1_000, 250, 500, 1_000]);
+}
+
+#[test]
+fn unknown_commands_are_an_error() {
+    assert!(run_bench("no-such-command", None, 1, 0, false, false).is_err());
+    assert!(!bench_command_names().contains(&"bench"));
+}
+
+#[test]
+fn the_cli_reports_an_output_it_cannot_write() {
+    // a file where a directory is expected, which is never created
+    let file = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("bench_not_a_dir");
+    std::fs::write(&file, "").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args([
+            "bench",
+            "rotating-list",
+            "--sizes",
+            "10",
+            "--iterations",
+            "1",
+        ])
+        .arg("--output")
+        .arg(file.join("bench.txt"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error:"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
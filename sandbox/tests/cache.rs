@@ -0,0 +1,165 @@
+/*
+ * The result cache: keyed by the command, its arguments and its input whatever its layout, and
+ * pruned by age.
+ */
+use dat_cli::{
+    cache_key, parse_command, run_command_with_options, Cache, CommandContext, Invocation,
+    RunOptions,
+};
+use serde_json::json;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn key(args: &[&str]) -> String {
+    let invocation = parse_command(args).unwrap();
+    let context = CommandContext::default();
+    cache_key(&invocation, &context).unwrap().unwrap()
+}
+
+fn job_sequencing(path: &Path, mode: &str) -> Invocation {
+    parse_command([
+        "job-sequencing",
+        "--json-file-path",
+        path.to_str().unwrap(),
+        "--mode",
+        mode,
+    ])
+    .unwrap()
+}
+
+#[test]
+fn the_key_depends_on_the_problem_not_its_layout() {
+    let dir = temp_dir("cache_key");
+    let compact = dir.join("jobs.json");
+    fs::write(
+        &compact,
+        r#"{"items":[{"title":"a","release":0,"processing_time":2,"deadline":4,"profit":10}]}"#,
+    )
+    .unwrap();
+    let reordered = dir.join("jobs.yaml");
+    fs::write(
+        &reordered,
+        "items:\n  - profit: 10\n    deadline: 4\n    processing_time: 2\n    release: 0\n    title: a\n",
+    )
+    .unwrap();
+    let args = |path: &Path, mode: &'static str| {
+        vec![
+            "job-sequencing".to_string(),
+            format!("--json-file-path={}", path.display()),
+            format!("--mode={}", mode),
+        ]
+    };
+    let key_of = |args: Vec<String>| key(&args.iter().map(String::as_str).collect::<Vec<_>>());
+
+    assert_eq!(
+        key_of(args(&compact, "edf")),
+        key_of(args(&reordered, "edf"))
+    );
+    assert_ne!(
+        key_of(args(&compact, "edf")),
+        key_of(args(&compact, "profit"))
+    );
+    assert_eq!(key_of(args(&compact, "edf")).len(), 64);
+}
+
+#[test]
+fn csv_cells_are_keyed_as_their_fields() {
+    let dir = temp_dir("cache_key_csv");
+    let csv = dir.join("jobs.csv");
+    fs::write(&csv, "title,deadline,profit\n1,4,10\n").unwrap();
+    let json = dir.join("jobs.json");
+    fs::write(
+        &json,
+        r#"{"items":[{"title":"1","deadline":4,"profit":10}]}"#,
+    )
+    .unwrap();
+    let key_of = |path: &Path| {
+        cache_key(&job_sequencing(path, "edf"), &CommandContext::default())
+            .unwrap()
+            .unwrap()
+    };
+    assert_eq!(key_of(&csv), key_of(&json));
+}
+
+#[test]
+fn a_run_keeps_its_result_for_the_next_one() {
+    let dir = temp_dir("cache_run");
+    let input = dir.join("jobs.json");
+    fs::write(
+        &input,
+        json!({ "items": [
+            { "title": "job1", "release": 0, "processing_time": 2, "deadline": 4, "profit": 10 },
+        ] })
+        .to_string(),
+    )
+    .unwrap();
+    let cache = Cache::new(dir.join("results"));
+    let key = key(&[
+        "job-sequencing",
+        "--json-file-path",
+        input.to_str().unwrap(),
+        "--mode",
+        "edf",
+    ]);
+    assert_eq!(cache.get(&key), None);
+
+    let options = RunOptions {
+        cache: Some(cache.clone()),
+        ..RunOptions::default()
+    };
+    run_command_with_options(job_sequencing(&input, "edf"), &options).unwrap();
+    let cached = cache.get(&key).unwrap();
+    assert_eq!(cached["items"][0]["title"], "job1");
+
+    // the next run reports what is in the cache without solving the problem again
+    let planted = json!({ "planted": true });
+    cache.put(&key, "job-sequencing", &planted).unwrap();
+    run_command_with_options(job_sequencing(&input, "edf"), &options).unwrap();
+    assert_eq!(cache.get(&key), Some(planted));
+}
+
+#[test]
+fn prune_removes_old_and_foreign_entries() {
+    let dir = temp_dir("cache_prune");
+    let cache = Cache::new(&dir);
+    cache.put("fresh", "schedule", &json!([1])).unwrap();
+    fs::write(
+        dir.join("older.json"),
+        r#"{"version":"0.0.0","command":"schedule","result":[1]}"#,
+    )
+    .unwrap();
+    fs::write(dir.join("interrupted.123.partial"), "{").unwrap();
+
+    let pruned = cache.prune(Some(Duration::from_secs(3600))).unwrap();
+    assert_eq!(pruned.removed, 2);
+    assert_eq!(cache.get("fresh"), Some(json!([1])));
+
+    let pruned = cache.prune(None).unwrap();
+    assert_eq!(pruned.removed, 1);
+    assert_eq!(cache.get("fresh"), None);
+    assert_eq!(
+        Cache::new(dir.join("missing")).prune(None).unwrap().removed,
+        0
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn prune_skips_files_that_are_gone() {
+    let dir = temp_dir("cache_prune_gone");
+    let cache = Cache::new(&dir);
+    cache.put("kept", "schedule", &json!([1])).unwrap();
+    // listed, but there is nothing to read or remove, as when another prune got to it first
+    std::os::unix::fs::symlink(dir.join("nowhere"), dir.join("gone.json")).unwrap();
+    assert_eq!(cache.prune(None).unwrap().removed, 1);
+}
@@ -0,0 +1,49 @@
+/*
+ * The catalog has to stay true to the commands: the examples give the documented results and the
+ * modes are the ones the mode arguments accept.
+ */
+use dat_cli::{describe_command, describe_text, registry, run_example};
+
+#[test]
+fn examples_give_the_documented_results() {
+    for command in registry() {
+        let run = run_example(command).unwrap();
+        assert!(run.passed(), "{}: {:?}", command.name(), run.diff());
+    }
+}
+
+#[test]
+fn modes_match_the_mode_arguments() {
+    for command in registry() {
+        let modes: Vec<&str> = command.modes().iter().map(|mode| mode.name).collect();
+        assert!(!modes.is_empty(), "{}", command.name());
+        let Some(arg) = command
+            .args()
+            .into_iter()
+            .find(|arg| arg.get_id() == "mode" || arg.get_id() == "rotation_direction")
+        else {
+            continue;
+        };
+        let values: Vec<String> = arg
+            .get_possible_values()
+            .iter()
+            .map(|value| value.get_name().to_string())
+            .collect();
+        assert_eq!(modes, values, "{}", command.name());
+    }
+}
+
+#[test]
+fn descriptions_carry_the_metadata() {
+    let command = dat_cli::find_command("daily-temperature").unwrap();
+    let text = describe_text(command);
+    assert!(text.contains("Stack    time O(n), space O(n)"), "{}", text);
+    assert!(text.contains("--mode Stack"), "{}", text);
+
+    let description = describe_command(command);
+    assert_eq!(description["modes"][1]["name"], "Reverse");
+    assert_eq!(
+        description["example"]["output"],
+        serde_json::json!([1, 1, 4, 2, 1, 1, 0, 0])
+    );
+}
@@ -0,0 +1,63 @@
+/*
+ * Completions and man pages are generated from the full CLI, the registered commands included.
+ */
+use std::{fs, path::PathBuf, process::Command};
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn completions_cover_every_subcommand() {
+    for shell in ["bash", "zsh", "fish", "elvish", "powershell"] {
+        let script = run(&["completions", shell]);
+        for subcommand in ["job-sequencing", "sufficient-coverage-set", "bench", "repl"] {
+            assert!(
+                script.contains(subcommand),
+                "{} misses {}",
+                shell,
+                subcommand
+            );
+        }
+    }
+    // the arguments of the registered commands complete too
+    assert!(run(&["completions", "bash"]).contains("--json-file-path"));
+}
+
+#[test]
+fn an_unknown_shell_is_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args(["completions", "cmd"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn the_man_page_lists_the_subcommands() {
+    let page = run(&["man"]);
+    assert!(page.contains(".TH dat_cli 1"), "{}", page);
+    assert!(page.contains("job\\-sequencing"), "{}", page);
+}
+
+#[test]
+fn a_page_is_written_per_subcommand() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("man_pages");
+    let _ = fs::remove_dir_all(&dir);
+    run(&["man", "--output-dir", dir.to_str().unwrap()]);
+    for page in [
+        "dat_cli.1",
+        "dat_cli-job-sequencing.1",
+        "dat_cli-gen-schedule.1",
+        "dat_cli-config-show.1",
+    ] {
+        assert!(dir.join(page).is_file(), "missing {}", page);
+    }
+    let page = fs::read_to_string(dir.join("dat_cli-rotating-list.1")).unwrap();
+    assert!(page.contains(r"\-\-rotation\-direction"), "{}", page);
+}
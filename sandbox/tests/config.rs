@@ -0,0 +1,117 @@
+/*
+ * Defaults layered from the user config, the project config and the environment, with the command
+ * line above them all, and a broken config file reported instead of panicking.
+ */
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+const MODE_ENV: &str = "DAT_CLI_JOB_SEQUENCING_MODE";
+
+/// The result of a run, which the track-allocations feature prints along with its heap usage
+fn result_of(output: serde_json::Value) -> serde_json::Value {
+    match cfg!(feature = "track-allocations") {
+        true => output["result"].clone(),
+        false => output,
+    }
+}
+
+/// A project directory along with a user config directory of its own
+fn dirs(name: &str) -> (PathBuf, PathBuf) {
+    let root = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&root);
+    let project = root.join("project");
+    let xdg = root.join("xdg");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(xdg.join("dat_cli")).unwrap();
+    (project, xdg)
+}
+
+fn dat_cli(project: &Path, xdg: &Path, env: &[(&str, &str)], args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .arg("-q")
+        .args(args)
+        .current_dir(project)
+        .env("XDG_CONFIG_HOME", xdg)
+        .env_remove(MODE_ENV)
+        .envs(env.iter().copied())
+        .output()
+        .unwrap()
+}
+
+/// The `job-sequencing.mode` line of `config show`
+fn configured_mode(project: &Path, xdg: &Path, env: &[(&str, &str)]) -> String {
+    let output = dat_cli(project, xdg, env, &["config", "show"]);
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .find(|line| line.starts_with("job-sequencing.mode"))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn each_layer_overrides_the_ones_below() {
+    let (project, xdg) = dirs("config_layers");
+    assert!(configured_mode(&project, &xdg, &[]).contains("\"edf\" (built-in default)"));
+
+    fs::write(
+        xdg.join("dat_cli").join("config.toml"),
+        "[job-sequencing]\nmode = \"profit\"\n",
+    )
+    .unwrap();
+    let mode = configured_mode(&project, &xdg, &[]);
+    assert!(
+        mode.contains("\"profit\"") && mode.contains("config.toml"),
+        "{}",
+        mode
+    );
+
+    fs::write(
+        project.join("dat_cli.toml"),
+        "[job-sequencing]\nmode = \"edf\"\n",
+    )
+    .unwrap();
+    let mode = configured_mode(&project, &xdg, &[]);
+    assert!(
+        mode.contains("\"edf\"") && mode.contains("dat_cli.toml"),
+        "{}",
+        mode
+    );
+
+    let env = [(MODE_ENV, "profit")];
+    let mode = configured_mode(&project, &xdg, &env);
+    assert!(
+        mode.contains("\"profit\"") && mode.contains(MODE_ENV),
+        "{}",
+        mode
+    );
+
+    // the modes tell apart by the profit they make out of the same jobs
+    let jobs = Path::new(env!("CARGO_MANIFEST_DIR")).join("test/assets/job_sequencing.profit.json");
+    let total_profit = |mode: &[&str]| {
+        let mut args = vec!["job-sequencing", "-j", jobs.to_str().unwrap()];
+        args.extend(mode);
+        let output = dat_cli(&project, &xdg, &env, &args);
+        assert!(output.status.success(), "{:?}", output);
+        let result = result_of(serde_json::from_slice(&output.stdout).unwrap());
+        result["total_profit"].clone()
+    };
+    assert_eq!(total_profit(&[]), 142);
+    assert_eq!(total_profit(&["--mode", "edf"]), 19);
+}
+
+#[test]
+fn a_broken_config_is_an_error_not_a_panic() {
+    let (project, xdg) = dirs("config_broken");
+    fs::write(project.join("dat_cli.toml"), "not = [valid").unwrap();
+    let output = dat_cli(&project, &xdg, &[], &["list"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error: Invalid config"), "{}", stderr);
+    assert!(stderr.contains("dat_cli.toml"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
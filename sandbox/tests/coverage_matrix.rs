@@ -0,0 +1,57 @@
+/*
+ * Coverage matrices must agree with the evaluation: a problem is covered exactly when its matrix
+ * has no holes.
+ */
+use dat_cli::{
+    CancellationToken, CommandContext, CoverageCell, CoverageChart, SufficientCoverageProblemSet,
+};
+use serde_json::json;
+
+fn problems() -> SufficientCoverageProblemSet {
+    serde_json::from_value(json!({ "items": [
+        { "tickets": [[1, 2, 3], [1, 4, 6]], "target_coverage": { "numbers": [1, 2, 3, 4, 5], "min_numbers_to_cover": 2 }, "expected": true },
+        { "tickets": [[1, 2, 3], [5, 6, 7]], "target_coverage": { "numbers": [1, 2, 3, 4, 5], "min_numbers_to_cover": 2 }, "expected": false },
+    ] }))
+    .unwrap()
+}
+
+#[test]
+fn holes_match_the_evaluation() {
+    let problems = problems();
+    let chart = CoverageChart::new(&problems, &CancellationToken::new()).unwrap();
+    let covered: Vec<bool> = chart
+        .matrices
+        .iter()
+        .map(|matrix| matrix.holes() == 0)
+        .collect();
+    assert_eq!(
+        covered,
+        dat_cli::evaluate_sufficient_coverage(&problems, &CommandContext::default()).unwrap()
+    );
+
+    let matrix = &chart.matrices[1];
+    assert_eq!(matrix.combos[0], vec![1, 2]);
+    assert_eq!(
+        matrix.cells[0],
+        vec![CoverageCell::Direct, CoverageCell::Missed]
+    );
+    let hole = matrix.combos.iter().position(|c| c == &[4, 5]).unwrap();
+    assert_eq!(matrix.row_coverage(hole), CoverageCell::Missed);
+}
+
+#[test]
+fn rendering() {
+    let chart = CoverageChart::new(&problems(), &CancellationToken::new()).unwrap();
+    let ascii = chart.to_ascii();
+    assert!(ascii.contains("  4,5 ..  <- not covered"));
+    let svg = chart.to_svg();
+    // a cell per combination and ticket, and one per legend entry
+    assert_eq!(svg.matches("<rect").count(), 2 * 10 * 2 + 3);
+}
+
+#[test]
+fn cancelled_before_the_start() {
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    assert!(CoverageChart::new(&problems(), &cancellation).is_err());
+}
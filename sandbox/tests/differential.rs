@@ -0,0 +1,203 @@
+/*
+ * Differential tests, checking every algorithm against its brute-force oracle on thousands of
+ * random inputs. Failing inputs are shrunk by proptest to a minimal case.
+ *
+ * The inputs come from a fixed seed so failures reproduce, `PROPTEST_RNG_SEED` and
+ * `PROPTEST_CASES` override the seed and the number of cases.
+ */
+mod oracles;
+
+use dat_cli::CommandContext;
+use oracles::Job;
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+use proptest::test_runner::RngSeed;
+use serde_json::{json, Value};
+
+const SEED: u64 = 0xDA7C11;
+const CASES: u32 = 2000;
+
+fn config(cases: u32) -> ProptestConfig {
+    let mut config = ProptestConfig::default();
+    if std::env::var_os("PROPTEST_CASES").is_none() {
+        config.cases = cases;
+    }
+    if std::env::var_os("PROPTEST_RNG_SEED").is_none() {
+        config.rng_seed = RngSeed::Fixed(SEED);
+    }
+    // the seed is fixed, so there is nothing to persist
+    config.failure_persistence = None;
+    config
+}
+
+fn intervals() -> impl Strategy<Value = Vec<(i32, i32)>> {
+    prop::collection::vec(
+        (0..20i32, 1..6i32).prop_map(|(start, length)| (start, start + length)),
+        0..=10,
+    )
+}
+
+fn schedule_json(intervals: &[(i32, i32)]) -> Value {
+    let items: Vec<Value> = intervals
+        .iter()
+        .enumerate()
+        .map(|(i, (start, end))| json!({ "start": start, "end": end, "title": format!("task{}", i) }))
+        .collect();
+    json!({ "items": items })
+}
+
+fn jobs_json(jobs: &[Job]) -> Value {
+    let items: Vec<Value> = jobs
+        .iter()
+        .enumerate()
+        .map(|(i, (release, processing_time, deadline, profit))| {
+            json!({
+                "title": format!("job{}", i),
+                "release": release,
+                "processing_time": processing_time,
+                "deadline": deadline,
+                "profit": profit,
+            })
+        })
+        .collect();
+    json!({ "items": items })
+}
+
+fn run_jobs(jobs: &[Job], mode: &str) -> Value {
+    let schedule: dat_cli::JobSchedule = serde_json::from_value(jobs_json(jobs)).unwrap();
+    serde_json::to_value(
+        dat_cli::sequence_jobs(&schedule, mode, &CommandContext::default()).unwrap(),
+    )
+    .unwrap()
+}
+
+/// Checks that the sequenced jobs are run one at a time, none before its release
+fn check_sequence(jobs: &[Job], sequence: &Value) -> Result<(), TestCaseError> {
+    let mut time = i32::MIN;
+    for item in sequence["items"].as_array().unwrap() {
+        let title = item["title"].as_str().unwrap();
+        let (release, processing_time, _, _) = jobs[title["job".len()..].parse::<usize>().unwrap()];
+        let start = item["start"].as_i64().unwrap() as i32;
+        prop_assert!(start >= release, "{} starts before its release", title);
+        prop_assert!(
+            start >= time,
+            "{} starts before the previous job ended",
+            title
+        );
+        prop_assert_eq!(
+            item["end"].as_i64().unwrap() as i32,
+            start + processing_time
+        );
+        time = start + processing_time;
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(config(CASES))]
+
+    #[test]
+    fn schedule_picks_as_many_tasks_as_possible(intervals in intervals()) {
+        let schedule: dat_cli::Schedule = serde_json::from_value(schedule_json(&intervals)).unwrap();
+        let result = serde_json::to_value(dat_cli::get_optimal_schedule(&schedule, &CommandContext::default()).unwrap()).unwrap();
+        let picked: Vec<(i32, i32)> = result["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| (item["start"].as_i64().unwrap() as i32, item["end"].as_i64().unwrap() as i32))
+            .collect();
+
+        for (i, &a) in picked.iter().enumerate() {
+            for &b in picked[i + 1..].iter() {
+                prop_assert!(!oracles::overlaps(a, b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+        prop_assert_eq!(picked.len(), oracles::max_compatible_intervals(&intervals));
+    }
+
+    #[test]
+    fn daily_temperature_matches_scan(temperatures in prop::collection::vec(30..40i32, 0..40)) {
+        let expected = oracles::next_warmer(&temperatures);
+        for mode in ["Stack", "Reverse"] {
+            prop_assert_eq!(&dat_cli::get_daily_temperature(&temperatures, mode, &CommandContext::default()).unwrap(), &expected);
+        }
+    }
+
+    #[test]
+    fn rotating_list_matches_rotate_right(
+        numbers in prop::collection::vec(-100..100i32, 0..20),
+        k in 0..50i32,
+    ) {
+        prop_assert_eq!(
+            dat_cli::rotating_list(numbers.clone(), k, "right", &CommandContext::default()).unwrap(),
+            oracles::rotate_right(&numbers, k as usize)
+        );
+    }
+
+    #[test]
+    fn edf_minimizes_max_lateness_with_common_release(
+        release in 0..5i32,
+        jobs in prop::collection::vec((1..5i32, 0..15i32), 1..=6),
+    ) {
+        let jobs: Vec<Job> = jobs
+            .into_iter()
+            .map(|(processing_time, deadline)| (release, processing_time, deadline, 0))
+            .collect();
+        let sequence = run_jobs(&jobs, "edf");
+        check_sequence(&jobs, &sequence)?;
+        prop_assert_eq!(sequence["max_lateness"].as_i64().unwrap() as i32, oracles::min_max_lateness(&jobs));
+    }
+
+    #[test]
+    fn edf_minimizes_max_lateness_of_unit_jobs(
+        jobs in prop::collection::vec((0..6i32, 0..10i32), 1..=6),
+    ) {
+        let jobs: Vec<Job> = jobs
+            .into_iter()
+            .map(|(release, deadline)| (release, 1, deadline, 0))
+            .collect();
+        let sequence = run_jobs(&jobs, "edf");
+        check_sequence(&jobs, &sequence)?;
+        prop_assert_eq!(sequence["max_lateness"].as_i64().unwrap() as i32, oracles::min_max_lateness(&jobs));
+    }
+
+    #[test]
+    fn profit_mode_maximizes_on_time_profit(
+        jobs in prop::collection::vec((0..4i32, 0..6i32, 0..20i32), 0..=6),
+    ) {
+        let jobs: Vec<Job> = jobs
+            .into_iter()
+            .map(|(release, deadline, profit)| (release, 1, deadline, profit))
+            .collect();
+        let sequence = run_jobs(&jobs, "profit");
+        check_sequence(&jobs, &sequence)?;
+        prop_assert!(sequence["max_lateness"].as_i64().unwrap() <= 0);
+        prop_assert_eq!(sequence["total_profit"].as_i64().unwrap() as i32, oracles::max_on_time_profit(&jobs));
+    }
+}
+
+proptest! {
+    // the oracle is exponential in the number of target numbers, so fewer cases
+    #![proptest_config(config(CASES / 2))]
+
+    #[test]
+    fn sufficient_coverage_matches_exhaustive_check(
+        numbers in subsequence((1..=8u32).collect::<Vec<u32>>(), 0..=7),
+        tickets in prop::collection::vec(subsequence((1..=9u32).collect::<Vec<u32>>(), 1..=5), 0..=4),
+        // a combination of no numbers is meaningless
+        k in 1..=4usize,
+    ) {
+        let problem_set: dat_cli::SufficientCoverageProblemSet = serde_json::from_value(json!({
+            "items": [{
+                "tickets": tickets,
+                "target_coverage": { "numbers": numbers, "min_numbers_to_cover": k },
+                "expected": false,
+            }]
+        }))
+        .unwrap();
+        prop_assert_eq!(
+            dat_cli::evaluate_sufficient_coverage(&problem_set, &CommandContext::default()).unwrap(),
+            vec![oracles::is_sufficiently_covered(&tickets, &numbers, k)]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * Checks the C API: the checked-in header must match the one cbindgen generates from src/ffi.rs,
+ * and the C test program must build and pass against the library.
+ *
+ * Run with `DAT_CLI_BLESS=1` to update the header after changing the C API. Without a C compiler
+ * the C test program is skipped, unless `CI` is set.
+ */
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const BLESS_ENV: &str = "DAT_CLI_BLESS";
+
+fn manifest_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn header_path() -> PathBuf {
+    manifest_dir().join("include").join("dat_cli.h")
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let config = cbindgen::Config::from_file(manifest_dir().join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(manifest_dir().join("src").join("ffi.rs"))
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+
+    if std::env::var_os(BLESS_ENV).is_some_and(|value| !value.is_empty() && value != "0") {
+        fs::create_dir_all(header_path().parent().unwrap()).unwrap();
+        fs::write(header_path(), generated).unwrap();
+        return;
+    }
+    let checked_in = fs::read_to_string(header_path()).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "{} is out of date, run with {}=1 to update it",
+        header_path().display(),
+        BLESS_ENV
+    );
+}
+
+/// Directory the library was built into, next to the directory of this test binary
+fn library_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    exe.parent().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[cfg(unix)]
+#[test]
+fn c_program_passes() {
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let program = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi_test");
+    let library_dir = library_dir();
+
+    let build = Command::new(&compiler)
+        .arg(manifest_dir().join("test").join("c").join("ffi_test.c"))
+        .arg(format!("-I{}", manifest_dir().join("include").display()))
+        .arg(format!("-L{}", library_dir.display()))
+        .arg("-ldat_cli")
+        .arg(format!("-Wl,-rpath,{}", library_dir.display()))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-o")
+        .arg(&program)
+        .output();
+    let build = match build {
+        Ok(build) => build,
+        // a missing compiler must not let CI pass without ever running the C test program
+        Err(e) if std::env::var_os("CI").is_some() => {
+            panic!("cannot run {} to build the C test program: {}", compiler, e)
+        }
+        Err(e) => {
+            eprintln!(
+                "skipping the C test program, cannot run {}: {}",
+                compiler, e
+            );
+            return;
+        }
+    };
+    assert!(
+        build.status.success(),
+        "building the C test program failed:\n{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+
+    let run = Command::new(&program).output().unwrap();
+    assert!(
+        run.status.success(),
+        "the C test program failed:\n{}{}",
+        String::from_utf8_lossy(&run.stdout),
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,61 @@
+/*
+ * Charts of the schedule command: every task shows up, the rejected ones with what blocked them.
+ */
+use dat_cli::{BarStatus, CancellationToken, GanttChart, Schedule};
+use serde_json::json;
+
+fn schedule() -> Schedule {
+    serde_json::from_value(json!({ "items": [
+        { "title": "a", "start": 0, "end": 10 },
+        { "title": "b", "start": 5, "end": 15 },
+        { "title": "c", "start": 10, "end": 20 },
+    ] }))
+    .unwrap()
+}
+
+fn selection(schedule: &Schedule) -> GanttChart {
+    GanttChart::selection(schedule, &CancellationToken::new()).unwrap()
+}
+
+#[test]
+fn selection_marks_picked_and_blocked_tasks() {
+    let chart = selection(&schedule());
+    let outcomes: Vec<(&str, BarStatus, Option<&str>)> = chart
+        .rows
+        .iter()
+        .map(|row| (row.label.as_str(), row.bars[0].status, row.note.as_deref()))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("a", BarStatus::Selected, None),
+            ("b", BarStatus::Rejected, Some("blocked by a")),
+            ("c", BarStatus::Selected, None),
+        ]
+    );
+
+    let ascii = chart.to_ascii();
+    assert_eq!(ascii.lines().count(), 4);
+    assert!(ascii.lines().next().unwrap().starts_with("a |####"));
+    let svg = chart.to_svg();
+    assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+    assert_eq!(svg.matches("<rect").count(), 3);
+}
+
+#[test]
+fn empty_and_extreme_schedules() {
+    let empty: Schedule = serde_json::from_value(json!({ "items": [] })).unwrap();
+    assert_eq!(selection(&empty).to_ascii(), "(no tasks)\n");
+    let wide: Schedule = serde_json::from_value(json!({ "items": [
+        { "title": "all", "start": i32::MIN, "end": i32::MAX },
+    ] }))
+    .unwrap();
+    assert!(selection(&wide).to_ascii().contains(&"#".repeat(60)));
+}
+
+#[test]
+fn a_cancelled_chart_stops() {
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    assert!(GanttChart::selection(&schedule(), &cancellation).is_err());
+}
@@ -0,0 +1,49 @@
+/*
+ * `gen` has a subcommand for every command with a generator, and the files generated for the file
+ * based commands are inputs those commands accept.
+ */
+use dat_cli::registry;
+use std::{fs, path::PathBuf, process::Command};
+
+fn dat_cli(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .arg("-q")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn every_generated_input_is_accepted_by_its_command() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("gen");
+    fs::create_dir_all(&dir).unwrap();
+    let generated: Vec<_> = registry()
+        .into_iter()
+        .filter(|command| command.generator().is_some())
+        .collect();
+    assert_eq!(generated.len(), registry().len());
+    for command in generated {
+        let path = dir.join(format!("{}.json", command.name()));
+        let output = dat_cli(&["gen", command.name(), "-O", path.to_str().unwrap()]);
+        assert!(output.status.success(), "{:?}", output);
+        if !command.takes_input() {
+            // numbers separated by commas, passed as an argument instead
+            assert!(!fs::read_to_string(&path).unwrap().trim().is_empty());
+            continue;
+        }
+        let output = dat_cli(&[command.name(), "-j", path.to_str().unwrap()]);
+        assert!(output.status.success(), "{}: {:?}", command.name(), output);
+    }
+}
+
+#[test]
+fn the_same_seed_generates_the_same_input() {
+    let first = dat_cli(&["gen", "schedule", "--seed", "7", "-l", "5"]);
+    let second = dat_cli(&["gen", "schedule", "-l", "5", "--seed", "7"]);
+    assert!(first.status.success());
+    assert_eq!(first.stdout, second.stdout);
+    assert_ne!(
+        first.stdout,
+        dat_cli(&["gen", "schedule", "-l", "5"]).stdout
+    );
+}
@@ -0,0 +1,136 @@
+/*
+ * Golden-file tests over the fixtures in `test/assets`.
+ *
+ * Every `<command>[.<mode>].json` input is run through the matching command, e.g.
+ * `sufficient_coverage_set.json` through `sufficient-coverage-set` and `job_sequencing.profit.json`
+ * through `job-sequencing --mode profit`, and the result is compared against the snapshot next to
+ * it in `<command>[.<mode>].expected.json`. New fixtures are picked up automatically.
+ *
+ * Run with `DAT_CLI_BLESS=1` to write the snapshots from the current results instead:
+ *
+ *   DAT_CLI_BLESS=1 cargo test --test golden
+ */
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use dat_cli::CommandContext;
+use serde_json::Value;
+
+const BLESS_ENV: &str = "DAT_CLI_BLESS";
+const EXPECTED_SUFFIX: &str = ".expected.json";
+
+fn assets_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test")
+        .join("assets")
+}
+
+fn is_blessing() -> bool {
+    std::env::var_os(BLESS_ENV).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// The inputs in the assets directory, sorted so failures are reported in a stable order
+fn fixtures() -> Vec<PathBuf> {
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(assets_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.ends_with(".json") && !name.ends_with(EXPECTED_SUFFIX)
+        })
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+/// Command line for a fixture, from its file name
+fn fixture_args(fixture: &Path) -> Result<Vec<String>, String> {
+    let stem = fixture.file_stem().unwrap().to_string_lossy();
+    let (command_name, mode) = match stem.split_once('.') {
+        Some((command_name, mode)) => (command_name, Some(mode)),
+        None => (stem.as_ref(), None),
+    };
+    let command_name = command_name.replace('_', "-");
+    let command = dat_cli::find_command(&command_name)
+        .ok_or_else(|| format!("no command named {}", command_name))?;
+
+    let mut args = vec![
+        command_name,
+        "--json-file-path".to_string(),
+        fixture.display().to_string(),
+    ];
+    if let Some(mode) = mode {
+        if !command.args().iter().any(|arg| arg.get_id() == "mode") {
+            return Err(format!("{} has no modes, but got {}", command.name(), mode));
+        }
+        args.extend(["--mode".to_string(), mode.to_string()]);
+    }
+    Ok(args)
+}
+
+fn run_fixture(fixture: &Path) -> Result<Value, String> {
+    let invocation = dat_cli::parse_command(fixture_args(fixture)?).map_err(|e| e.to_string())?;
+    let context = CommandContext::default();
+    dat_cli::execute_command(&invocation, &context).map_err(|e| e.to_string())
+}
+
+fn expected_path(fixture: &Path) -> PathBuf {
+    let stem = fixture.file_stem().unwrap().to_string_lossy();
+    fixture.with_file_name(format!("{}{}", stem, EXPECTED_SUFFIX))
+}
+
+/// Compares the result of a fixture against its snapshot, or writes the snapshot when blessing
+fn check_fixture(fixture: &Path) -> Result<(), String> {
+    let actual = run_fixture(fixture)?;
+    let expected_path = expected_path(fixture);
+    if is_blessing() {
+        let snapshot = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+        return fs::write(&expected_path, snapshot).map_err(|e| e.to_string());
+    }
+
+    let content = fs::read_to_string(&expected_path).map_err(|e| {
+        format!(
+            "cannot read {}: {}, run with {}=1 to create it",
+            expected_path.display(),
+            e,
+            BLESS_ENV
+        )
+    })?;
+    let expected: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("invalid snapshot {}: {}", expected_path.display(), e))?;
+    let differences = dat_cli::diff_json(&expected, &actual);
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(differences.join("\n    "))
+    }
+}
+
+#[test]
+fn fixtures_match_snapshots() {
+    let fixtures = fixtures();
+    assert!(
+        !fixtures.is_empty(),
+        "no fixtures in {}",
+        assets_dir().display()
+    );
+
+    let failures: Vec<String> = fixtures
+        .iter()
+        .filter_map(|fixture| {
+            check_fixture(fixture)
+                .err()
+                .map(|e| format!("{}:\n    {}", fixture.display(), e))
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "{} of {} fixtures failed, run with {}=1 to accept the new results\n{}",
+        failures.len(),
+        fixtures.len(),
+        BLESS_ENV,
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,94 @@
+/*
+ * Every input format deserializes into the same types, and errors point at where they happened.
+ */
+use dat_cli::{parse_input, CommandContext, InputFormat, Schedule, SufficientCoverageProblemSet};
+use serde_json::{json, Value};
+
+fn schedule(content: &str, format: InputFormat) -> Value {
+    let schedule: Schedule = parse_input(content, format).unwrap();
+    serde_json::to_value(schedule).unwrap()
+}
+
+#[test]
+fn schedules_in_every_format() {
+    let expected = json!({ "items": [
+        { "start": 1, "end": 3, "title": "a, b" },
+        { "start": 2, "end": 4, "title": "2024" },
+    ] });
+    let json = r#"{ "items": [
+        { "title": "a, b", "start": 1, "end": 3 },
+        { "title": "2024", "start": 2, "end": 4 }
+    ] }"#;
+    let yaml = "items:\n  - { title: 'a, b', start: 1, end: 3 }\n  - { title: '2024', start: 2, end: 4 }\n";
+    let toml = "[[items]]\ntitle = 'a, b'\nstart = 1\nend = 3\n\n[[items]]\ntitle = '2024'\nstart = 2\nend = 4\n";
+    let csv = "title, start, end\r\n\"a, b\", 1, 3\r\n\r\n2024, 2, 4\r\n";
+    for (content, format) in [
+        (json, InputFormat::Json),
+        (yaml, InputFormat::Yaml),
+        (toml, InputFormat::Toml),
+        (csv, InputFormat::Csv),
+    ] {
+        assert_eq!(schedule(content, format), expected, "{:?}", format);
+    }
+}
+
+#[test]
+fn nested_inputs_in_yaml_and_toml() {
+    let yaml = "items:\n  - tickets: [[1, 2], [2, 3]]\n    target_coverage: { numbers: [1, 2, 3], min_numbers_to_cover: 2 }\n    expected: true\n";
+    let toml = "[[items]]\ntickets = [[1, 2], [2, 3]]\ntarget_coverage = { numbers = [1, 2, 3], min_numbers_to_cover = 2 }\nexpected = true\n";
+    for (content, format) in [(yaml, InputFormat::Yaml), (toml, InputFormat::Toml)] {
+        let problems: SufficientCoverageProblemSet = parse_input(content, format).unwrap();
+        assert_eq!(
+            dat_cli::evaluate_sufficient_coverage(&problems, &CommandContext::default()).unwrap(),
+            vec![true]
+        );
+    }
+}
+
+#[test]
+fn csv_cells_are_typed_by_the_target() {
+    // without a type to go by, whatever reads as a number is one
+    let value: Value =
+        parse_input("title,start,note\n7,1,\n\"8\",2.5,x\n", InputFormat::Csv).unwrap();
+    assert_eq!(
+        value,
+        json!({ "items": [
+            { "title": 7, "start": 1 },
+            { "title": 8, "start": 2.5, "note": "x" },
+        ] })
+    );
+    assert_eq!(
+        schedule("title,start,end\n7,1,2\n", InputFormat::Csv),
+        json!({ "items": [{ "start": 1, "end": 2, "title": "7" }] })
+    );
+}
+
+#[test]
+fn errors_carry_line_and_column() {
+    let error = |content: &str, format: InputFormat| {
+        parse_input::<Schedule>(content, format)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(
+        error("{ \"items\": [\n  { \"title\": 1 }\n] }", InputFormat::Json)
+            .contains("line 2 column 14")
+    );
+    assert!(
+        error("items:\n  - title: a\n    start: x\n", InputFormat::Yaml)
+            .contains("line 3 column 12")
+    );
+    assert!(error("[[items]]\ntitle = 3\n", InputFormat::Toml).contains("line 2, column 9"));
+    assert_eq!(
+        error("title,start,end\na,1,x\n", InputFormat::Csv),
+        "invalid type: string \"x\", expected i32 in column `end` at line 2"
+    );
+    assert_eq!(
+        error("title,start,end\na,1,2\nb,2\n", InputFormat::Csv),
+        "expected 3 cells as in the header, got 2 at line 3"
+    );
+    assert_eq!(
+        error("title,start\na,1\n", InputFormat::Csv),
+        "missing field `end` at line 2"
+    );
+}
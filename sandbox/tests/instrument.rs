@@ -0,0 +1,78 @@
+/*
+ * Operation counts: they only add up inside a measure, and their growth tells the linear modes
+ * from the quadratic ones.
+ */
+use dat_cli::{measure, run_bench, CommandContext, Operation};
+
+fn get_daily_temperature(temperatures: &[i32], mode: &str) -> std::io::Result<Vec<usize>> {
+    dat_cli::get_daily_temperature(temperatures, mode, &CommandContext::default())
+}
+
+/// Colder every day up to a warm last day, the worst case of `Reverse`
+fn falling(length: usize) -> Vec<i32> {
+    let mut temperatures: Vec<i32> = (0..length as i32).map(|i| 100 - i).collect();
+    temperatures[length - 1] = 101;
+    temperatures
+}
+
+#[test]
+fn counts_only_inside_a_measure() {
+    get_daily_temperature(&falling(10), "Stack").unwrap();
+    let (result, counts) = measure(|| get_daily_temperature(&[73, 74, 75], "Stack").unwrap());
+    assert_eq!(result, vec![1, 1, 0]);
+    assert_eq!(counts.get(Operation::StackPush), 3);
+    assert_eq!(counts.get(Operation::StackPop), 2);
+    assert_eq!(counts.get(Operation::NodeHop), 0);
+}
+
+#[test]
+fn the_outer_measure_counts_again_after_an_inner_one() {
+    let ((_, inner), outer) = measure(|| {
+        let inner = measure(|| get_daily_temperature(&[73, 74], "Stack").unwrap());
+        get_daily_temperature(&[73, 74, 75], "Stack").unwrap();
+        inner
+    });
+    assert_eq!(inner.get(Operation::StackPush), 2);
+    assert_eq!(outer.get(Operation::StackPush), 3);
+}
+
+#[test]
+fn reverse_compares_every_pair_in_its_worst_case() {
+    let (_, stack) = measure(|| get_daily_temperature(&falling(100), "Stack").unwrap());
+    let (_, reverse) = measure(|| get_daily_temperature(&falling(100), "Reverse").unwrap());
+    assert!(stack.get(Operation::Comparison) < 2 * 100);
+    assert_eq!(reverse.get(Operation::Comparison), 100 * 99 / 2);
+}
+
+#[test]
+fn bench_fits_the_growth_of_the_counts() {
+    let exponent = |report: &dat_cli::BenchReport, mode: &str, operation: Operation| {
+        let growth = report.growth.iter().find(|growth| growth.mode == mode);
+        growth
+            .and_then(|growth| {
+                growth
+                    .operations
+                    .iter()
+                    .find(|growth| growth.operation == operation)
+            })
+            .and_then(|growth| growth.exponent)
+            .unwrap()
+    };
+
+    let report = run_bench(
+        "daily-temperature",
+        Some(&[100, 200, 400]),
+        1,
+        0,
+        true,
+        true,
+    )
+    .unwrap();
+    assert!(exponent(&report, "Stack", Operation::Comparison) < 1.2);
+    assert!(exponent(&report, "Reverse", Operation::Comparison) > 1.8);
+
+    let report = run_bench("schedule", Some(&[100, 200, 400]), 1, 0, false, true).unwrap();
+    assert!(exponent(&report, "greedy", Operation::Comparison) > 1.8);
+
+    assert!(run_bench("schedule", Some(&[100]), 1, 0, true, false).is_err());
+}
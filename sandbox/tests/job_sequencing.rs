@@ -0,0 +1,121 @@
+/*
+ * Job sequencing in both modes: the order of the jobs, how ties are broken, which jobs profit mode
+ * gives up on, and the inputs that are rejected instead of sequenced.
+ */
+use dat_cli::{sequence_jobs, CommandContext, JobSchedule};
+use serde_json::{json, Value};
+
+fn run(jobs: Value, mode: &str) -> Result<Value, std::io::Error> {
+    let schedule: JobSchedule = serde_json::from_value(json!({ "items": jobs })).unwrap();
+    sequence_jobs(&schedule, mode, &CommandContext::default())
+        .map(|sequence| serde_json::to_value(sequence).unwrap())
+}
+
+fn titles(items: &Value) -> Vec<&str> {
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["title"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn edf_runs_the_earliest_deadline_and_idles_until_a_release() {
+    let result = run(
+        json!([
+            { "title": "late", "release": 0, "processing_time": 2, "deadline": 10 },
+            { "title": "urgent", "release": 0, "processing_time": 1, "deadline": 1 },
+            { "title": "after", "release": 5, "processing_time": 1, "deadline": 6 },
+        ]),
+        "edf",
+    )
+    .unwrap();
+    assert_eq!(titles(&result["items"]), ["urgent", "late", "after"]);
+    assert_eq!(result["items"][2]["start"], 5);
+    assert_eq!(result["max_lateness"], 0);
+}
+
+#[test]
+fn edf_ties_keep_the_input_order() {
+    let result = run(
+        json!([
+            { "title": "b", "processing_time": 1, "deadline": 1 },
+            { "title": "a", "processing_time": 1, "deadline": 1 },
+            { "title": "c", "processing_time": 1, "deadline": 1 },
+        ]),
+        "edf",
+    )
+    .unwrap();
+    assert_eq!(titles(&result["items"]), ["b", "a", "c"]);
+    assert_eq!(result["max_lateness"], 2);
+}
+
+#[test]
+fn profit_gives_up_the_least_profitable_jobs_that_cannot_fit() {
+    let result = run(
+        json!([
+            { "title": "small", "deadline": 1, "profit": 5 },
+            { "title": "big", "deadline": 1, "profit": 50 },
+            { "title": "later", "deadline": 2, "profit": 10 },
+            // a deadline before any job can end
+            { "title": "impossible", "deadline": 0, "profit": 100 },
+        ]),
+        "profit",
+    )
+    .unwrap();
+    assert_eq!(titles(&result["items"]), ["big", "later"]);
+    assert_eq!(titles(&result["rejected"]), ["impossible", "small"]);
+    assert_eq!(result["total_profit"], 60);
+}
+
+#[test]
+fn profit_ties_keep_the_first_job() {
+    let result = run(
+        json!([
+            { "title": "first", "deadline": 1, "profit": 10 },
+            { "title": "second", "deadline": 1, "profit": 10 },
+        ]),
+        "profit",
+    )
+    .unwrap();
+    assert_eq!(titles(&result["items"]), ["first"]);
+    assert_eq!(titles(&result["rejected"]), ["second"]);
+}
+
+#[test]
+fn profit_only_takes_unit_jobs() {
+    let error = run(
+        json!([{ "title": "long", "processing_time": 2, "deadline": 4 }]),
+        "profit",
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("only supports unit jobs"));
+}
+
+#[test]
+fn processing_times_below_one_are_rejected() {
+    for processing_time in [0, -3] {
+        let error = run(
+            json!([{ "title": "job", "processing_time": processing_time, "deadline": 4 }]),
+            "edf",
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("at least 1"), "{}", error);
+    }
+}
+
+#[test]
+fn times_past_the_largest_integer_are_an_error() {
+    let error = run(
+        json!([{ "title": "job", "release": i32::MAX, "processing_time": 5, "deadline": 4 }]),
+        "edf",
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("largest supported time"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,137 @@
+/*
+ * Logging from the command line: levels from -v and -q, JSON lines, the rolling log file and the
+ * per-module levels of DAT_CLI_LOG. Logs only ever go to stderr, so stdout keeps the result alone.
+ */
+use std::{fs, path::PathBuf, process::Command};
+
+const ROTATE: [&str; 5] = ["rotating-list", "-n", "1,2,3", "-k", "1"];
+
+/// The result of a run, which the track-allocations feature prints along with its heap usage
+fn result_of(output: serde_json::Value) -> serde_json::Value {
+    match cfg!(feature = "track-allocations") {
+        true => output["result"].clone(),
+        false => output,
+    }
+}
+
+/// Runs the CLI with the flags before the rotating-list invocation, returning its stderr
+fn stderr_of(flags: &[&str], env: &[(&str, &str)]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args(flags)
+        .args(ROTATE)
+        .env_remove("DAT_CLI_LOG")
+        .envs(env.iter().copied())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let result = result_of(serde_json::from_slice(&output.stdout).unwrap());
+    assert_eq!(result, serde_json::json!([3, 1, 2]));
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn verbosity_sets_the_level() {
+    let info = stderr_of(&[], &[]);
+    assert!(info.contains(" INFO -- Rotating list"), "{}", info);
+    assert!(!info.contains("DEBUG"), "{}", info);
+
+    let debug = stderr_of(&["-v"], &[]);
+    assert!(debug.contains("DEBUG"), "{}", debug);
+
+    assert_eq!(stderr_of(&["-q"], &[]), "");
+    assert_eq!(stderr_of(&["-qqq"], &[]), "");
+}
+
+#[test]
+fn verbose_and_quiet_conflict() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args(["-v", "-q"])
+        .args(ROTATE)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn json_logs_are_one_object_per_line() {
+    let stderr = stderr_of(&["-v", "--log-format", "json"], &[]);
+    let records: Vec<serde_json::Value> = stderr
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(records.iter().any(|record| record["level"] == "INFO"
+        && record["module_path"] == "dat_cli::commands::rotating_list"));
+}
+
+#[test]
+fn module_levels_override_the_flags() {
+    // the commands are quieted below the rest of the crate
+    let stderr = stderr_of(&["-v"], &[("DAT_CLI_LOG", "dat_cli::commands=warn")]);
+    assert!(stderr.contains("[dat_cli] DEBUG"), "{}", stderr);
+    assert!(!stderr.contains("dat_cli::commands"), "{}", stderr);
+
+    // a bare level applies everywhere
+    assert_eq!(stderr_of(&["-v"], &[("DAT_CLI_LOG", "error")]), "");
+}
+
+#[test]
+fn the_log_file_rolls_over() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("logging_file");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let log_file = dir.join("dat_cli.log");
+    let log_file_arg = log_file.to_str().unwrap();
+    for _ in 0..3 {
+        stderr_of(
+            &[
+                "-v",
+                "--log-file",
+                log_file_arg,
+                "--log-file-size",
+                "200",
+                "--log-file-count",
+                "2",
+            ],
+            &[],
+        );
+    }
+    assert!(fs::read_to_string(&log_file).unwrap().contains("DEBUG"));
+    assert!(dir.join("dat_cli.log.0").is_file());
+    // only the configured number of rolled over files is kept
+    assert!(!dir.join("dat_cli.log.2").exists());
+}
+
+#[test]
+fn quietly_only_quiets_its_own_thread() {
+    assert!(!dat_cli::is_quiet());
+    dat_cli::quietly(|| {
+        assert!(dat_cli::is_quiet());
+        std::thread::spawn(|| assert!(!dat_cli::is_quiet()))
+            .join()
+            .unwrap();
+    });
+    assert!(!dat_cli::is_quiet());
+}
+
+#[test]
+fn a_log_file_that_cannot_be_written_is_an_error() {
+    // the directory is created when missing, but not under a file
+    let file = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("logging_not_a_dir");
+    fs::write(&file, "").unwrap();
+    let log_file = file.join("dat_cli.log");
+    let output = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .arg("--log-file")
+        .arg(&log_file)
+        .args(ROTATE)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("error: cannot write the log file"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,139 @@
+/*
+ * Brute-force reference implementations of every algorithm. They are slow, but simple enough to
+ * be obviously correct, so the real implementations are checked against them on small inputs.
+ */
+
+/// A job as (release, processing time, deadline, profit)
+pub type Job = (i32, i32, i32, i32);
+
+/// Whether two intervals share any time, touching intervals do not overlap
+pub fn overlaps(a: (i32, i32), b: (i32, i32)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+/// Largest number of pairwise non-overlapping intervals, trying every subset
+pub fn max_compatible_intervals(intervals: &[(i32, i32)]) -> usize {
+    (0u32..1 << intervals.len())
+        .filter(|mask| {
+            let chosen: Vec<(i32, i32)> = intervals
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, &interval)| interval)
+                .collect();
+            chosen
+                .iter()
+                .enumerate()
+                .all(|(i, &a)| chosen[i + 1..].iter().all(|&b| !overlaps(a, b)))
+        })
+        .map(|mask| mask.count_ones() as usize)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Days to wait for a warmer temperature, scanning ahead from every day
+pub fn next_warmer(temperatures: &[i32]) -> Vec<usize> {
+    (0..temperatures.len())
+        .map(|i| {
+            (i + 1..temperatures.len())
+                .find(|&j| temperatures[j] > temperatures[i])
+                .map_or(0, |j| j - i)
+        })
+        .collect()
+}
+
+pub fn rotate_right(numbers: &[i32], k: usize) -> Vec<i32> {
+    let mut rotated = numbers.to_vec();
+    if !rotated.is_empty() {
+        rotated.rotate_right(k % numbers.len());
+    }
+    rotated
+}
+
+/**
+ * Whether every combination of `k` target numbers is covered. A ticket covers a combination when
+ * it holds `k` of the target numbers, at least one of which is in the combination. Checks every
+ * combination against every set of `k` target numbers held by every ticket.
+ */
+pub fn is_sufficiently_covered(tickets: &[Vec<u32>], numbers: &[u32], k: usize) -> bool {
+    let subsets_of_size = |size: usize| {
+        (0u32..1 << numbers.len()).filter(move |mask| mask.count_ones() as usize == size)
+    };
+    // the target numbers on each ticket, as a bit mask over `numbers`
+    let ticket_masks: Vec<u32> = tickets
+        .iter()
+        .map(|ticket| {
+            numbers
+                .iter()
+                .enumerate()
+                .filter(|(_, number)| ticket.contains(number))
+                .fold(0, |mask, (i, _)| mask | 1 << i)
+        })
+        .collect();
+
+    subsets_of_size(k).all(|combination| {
+        ticket_masks.iter().any(|&ticket| {
+            subsets_of_size(k).any(|held| held & !ticket == 0 && held & combination != 0)
+        })
+    })
+}
+
+/// Calls `visit` with every order of `0..n`
+fn for_each_permutation(n: usize, visit: &mut impl FnMut(&[usize])) {
+    fn permute(order: &mut Vec<usize>, used: &mut Vec<bool>, visit: &mut impl FnMut(&[usize])) {
+        if order.len() == used.len() {
+            visit(order);
+            return;
+        }
+        for i in 0..used.len() {
+            if !used[i] {
+                used[i] = true;
+                order.push(i);
+                permute(order, used, visit);
+                order.pop();
+                used[i] = false;
+            }
+        }
+    }
+    permute(&mut Vec::with_capacity(n), &mut vec![false; n], visit);
+}
+
+/// Maximum lateness when the jobs run in the given order, each as soon as it is released
+fn max_lateness_in_order(jobs: &[Job], order: &[usize]) -> i32 {
+    let mut time = i32::MIN;
+    let mut max_lateness = i32::MIN;
+    for &i in order {
+        let (release, processing_time, deadline, _) = jobs[i];
+        time = time.max(release) + processing_time;
+        max_lateness = max_lateness.max(time - deadline);
+    }
+    max_lateness
+}
+
+/// Smallest maximum lateness over every order of the jobs, 0 without jobs
+pub fn min_max_lateness(jobs: &[Job]) -> i32 {
+    if jobs.is_empty() {
+        return 0;
+    }
+    let mut best = i32::MAX;
+    for_each_permutation(jobs.len(), &mut |order| {
+        best = best.min(max_lateness_in_order(jobs, order));
+    });
+    best
+}
+
+/// Largest total profit of a set of jobs that can all finish by their deadlines, trying every subset
+pub fn max_on_time_profit(jobs: &[Job]) -> i32 {
+    (0u32..1 << jobs.len())
+        .filter_map(|mask| {
+            let chosen: Vec<Job> = jobs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, &job)| job)
+                .collect();
+            (min_max_lateness(&chosen) <= 0).then(|| chosen.iter().map(|job| job.3).sum())
+        })
+        .max()
+        .unwrap_or(0)
+}
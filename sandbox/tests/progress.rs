@@ -0,0 +1,139 @@
+/*
+ * Long coverage checks report their progress per problem, and a timeout stops them with a report
+ * of how far they got. The other commands that check for cancellation stop too.
+ */
+use dat_cli::{
+    evaluate_sufficient_coverage, parse_command, parse_duration, run_command_with_options,
+    CommandContext, Progress, RunOptions, StopReason, Stopped, SufficientCoverageProblemSet,
+};
+use serde_json::json;
+use std::{fs, path::PathBuf, time::Duration};
+
+#[test]
+fn durations_take_a_unit() {
+    assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+    assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+    assert_eq!(parse_duration("2"), Ok(Duration::from_secs(2)));
+    assert!(parse_duration("2 days").is_err());
+    assert!(parse_duration("-1s").is_err());
+}
+
+#[test]
+fn every_problem_is_a_stage() {
+    let test_set: SufficientCoverageProblemSet = serde_json::from_value(json!({ "items": [
+        { "tickets": [[1, 2], [2, 3], [3, 4]], "target_coverage": { "numbers": [1, 2, 3, 4], "min_numbers_to_cover": 2 }, "expected": true },
+        { "tickets": [[1, 2]], "target_coverage": { "numbers": [1, 2, 3, 4], "min_numbers_to_cover": 3 }, "expected": false },
+    ] }))
+    .unwrap();
+    let progress = Progress::new();
+    let context = CommandContext {
+        progress: Some(&progress),
+        ..CommandContext::default()
+    };
+    let results = evaluate_sufficient_coverage(&test_set, &context).unwrap();
+    assert_eq!(results, vec![true, false]);
+
+    let stages = progress.stages();
+    assert_eq!((stages[0].done, stages[0].total), (6, 6));
+    assert_eq!(stages[0].outcome.as_deref(), Some("covered"));
+    // the first uncovered combination settles it
+    assert_eq!(stages[1].outcome.as_deref(), Some("not covered"));
+    assert!(stages[1].done < stages[1].total);
+}
+
+#[test]
+fn a_timeout_stops_with_a_partial_report() {
+    let numbers: Vec<u32> = (1..=30).collect();
+    let problem = json!({
+        "tickets": [numbers[..25], numbers[5..]],
+        "target_coverage": { "numbers": numbers, "min_numbers_to_cover": 5 },
+        "expected": true,
+    });
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("progress_coverage.json");
+    fs::write(&path, json!({ "items": [problem, problem] }).to_string()).unwrap();
+    let invocation = parse_command([
+        "sufficient-coverage-set",
+        "--json-file-path",
+        path.to_str().unwrap(),
+    ])
+    .unwrap();
+
+    let options = RunOptions {
+        timeout: Some(Duration::from_millis(50)),
+        ..RunOptions::default()
+    };
+    let error = run_command_with_options(invocation, &options).unwrap_err();
+    let stopped = error.downcast_ref::<Stopped>().unwrap();
+    assert_eq!(
+        stopped.reason,
+        StopReason::Timeout(Duration::from_millis(50))
+    );
+    assert_eq!(stopped.stages.len(), 2);
+    assert_eq!(stopped.stages[0].total, 142506);
+    assert!(!stopped.stages[1].started);
+    let report = stopped.to_string();
+    assert!(report.starts_with("timed out after 50ms\n"), "{}", report);
+    assert!(report.contains("problem 1: not started"), "{}", report);
+}
+
+#[test]
+fn cancelled_runs_stop_in_every_checking_command() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let schedule = dir.join("progress_schedule.json");
+    fs::write(
+        &schedule,
+        r#"{ "items": [ { "title": "a", "start": 0, "end": 10 } ] }"#,
+    )
+    .unwrap();
+    let jobs = dir.join("progress_jobs.json");
+    fs::write(
+        &jobs,
+        r#"{ "items": [ { "title": "a", "deadline": 1 }, { "title": "b", "deadline": 2 } ] }"#,
+    )
+    .unwrap();
+
+    for args in [
+        vec!["schedule", "--json-file-path", schedule.to_str().unwrap()],
+        vec![
+            "schedule",
+            "--json-file-path",
+            schedule.to_str().unwrap(),
+            "--render",
+            "ascii",
+        ],
+        vec![
+            "job-sequencing",
+            "--json-file-path",
+            jobs.to_str().unwrap(),
+            "--mode",
+            "edf",
+        ],
+        vec![
+            "job-sequencing",
+            "--json-file-path",
+            jobs.to_str().unwrap(),
+            "--mode",
+            "profit",
+        ],
+        vec![
+            "daily-temperature",
+            "--temperatures",
+            "73,74,75",
+            "--mode",
+            "Reverse",
+        ],
+    ] {
+        let options = RunOptions::default();
+        options.cancellation.cancel();
+        let error = run_command_with_options(parse_command(&args).unwrap(), &options).unwrap_err();
+        let stopped = error.downcast_ref::<Stopped>();
+        assert_eq!(
+            stopped.map(|stopped| stopped.reason),
+            Some(StopReason::Interrupted),
+            "{:?}: {}",
+            args,
+            error
+        );
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ * The REPL, driven line by line through stdin: datasets loaded once and referenced as `@name`,
+ * errors that do not end the session, and the history kept in $HOME.
+ */
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Runs a REPL session over the lines, returning its stdout and stderr
+fn session(home: &Path, lines: &[&str]) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .arg("repl")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOME", home)
+        .env_remove("DAT_CLI_LOG")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all((lines.join("\n") + "\n").as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn home(name: &str) -> PathBuf {
+    let home = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&home);
+    fs::create_dir_all(&home).unwrap();
+    home
+}
+
+/// The result of a run, which the track-allocations feature prints along with its heap usage
+fn result_of(output: serde_json::Value) -> serde_json::Value {
+    match cfg!(feature = "track-allocations") {
+        true => output["result"].clone(),
+        false => output,
+    }
+}
+
+#[test]
+fn commands_run_on_loaded_datasets() {
+    let (stdout, _) = session(
+        &home("repl_datasets"),
+        &[
+            "load schedule test/assets/schedule.json",
+            "datasets",
+            "schedule -j @schedule",
+            "unload schedule",
+            "datasets",
+            "exit",
+        ],
+    );
+    let (listed, rest) = stdout.split_once('\n').unwrap();
+    assert_eq!(listed, "@schedule");
+    // the result is the last output, as nothing is left to list after the unload
+    let result = result_of(serde_json::from_str(rest).unwrap());
+    let expected: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string("test/assets/schedule.expected.json").unwrap())
+            .unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn errors_do_not_end_the_session() {
+    let (stdout, stderr) = session(
+        &home("repl_errors"),
+        &[
+            "unload missing",
+            "schedule -j @missing",
+            "no-such-command",
+            "rotating-list -n 1,2,3 -k 1",
+            "quit",
+        ],
+    );
+    assert!(stderr.contains("Unknown dataset: missing"), "{}", stderr);
+    assert!(stderr.contains("unrecognized subcommand"), "{}", stderr);
+    let result = result_of(serde_json::from_str(&stdout).unwrap());
+    assert_eq!(result, serde_json::json!([3, 1, 2]));
+}
+
+#[test]
+fn history_is_kept_across_sessions() {
+    let home = home("repl_history");
+    session(&home, &["datasets", "exit"]);
+    let (stdout, _) = session(&home, &["history", "exit"]);
+    assert!(stdout.contains("1  datasets"), "{}", stdout);
+    assert!(stdout.contains("3  history"), "{}", stdout);
+    assert!(home.join(".dat_cli_history").is_file());
+}
@@ -0,0 +1,133 @@
+/*
+ * Drives the JSON-RPC session with in-memory input and output.
+ */
+use serde_json::{json, Value};
+
+/// Runs a session over the requests, returning the responses by id
+fn session(requests: &[Value]) -> Vec<Value> {
+    let input: String = requests
+        .iter()
+        .map(|request| request.to_string() + "\n")
+        .collect();
+    session_raw(&input)
+}
+
+fn session_raw(input: &str) -> Vec<Value> {
+    let mut output = Vec::new();
+    dat_cli::run_rpc(input.as_bytes(), &mut output).unwrap();
+    let mut responses: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // commands answer in the background, so in any order
+    responses.sort_by_key(|response| response["id"].to_string());
+    responses
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+/// The result of a command, which the track-allocations feature returns along with its heap usage
+fn result_of(result: &Value) -> Value {
+    match cfg!(feature = "track-allocations") {
+        true => result["result"].clone(),
+        false => result.clone(),
+    }
+}
+
+#[test]
+fn listing_and_describing_commands() {
+    let responses = session(&[
+        request(1, "listCommands", json!({})),
+        request(2, "describeCommand", json!({ "name": "job-sequencing" })),
+        request(3, "describeCommand", json!({ "name": "nothing" })),
+    ]);
+    assert_eq!(
+        responses[0]["result"].as_array().unwrap().len(),
+        dat_cli::registry().len()
+    );
+    assert_eq!(responses[1]["result"]["name"], "job-sequencing");
+    assert_eq!(
+        responses[1]["result"]["args"][0]["values"],
+        json!(["edf", "profit"])
+    );
+    assert_eq!(responses[2]["error"]["code"], -32602);
+}
+
+#[test]
+fn running_commands() {
+    let responses = session(&[
+        request(1, "rotating-list", json!({ "numbers": [1, 2, 3], "k": 1 })),
+        request(
+            2,
+            "schedule",
+            json!({ "input": { "items": [
+                { "start": 1, "end": 3, "title": "a" },
+                { "start": 2, "end": 4, "title": "b" },
+            ] } }),
+        ),
+        // a notification, which gets no response
+        json!({ "jsonrpc": "2.0", "method": "rotating-list", "params": { "numbers": [1], "k": 1 } }),
+    ]);
+    assert_eq!(responses.len(), 2);
+    assert_eq!(result_of(&responses[0]["result"]), json!([3, 1, 2]));
+    assert_eq!(
+        result_of(&responses[1]["result"]),
+        json!({ "items": [{ "start": 1, "end": 3, "title": "a" }] })
+    );
+}
+
+#[test]
+fn errors_are_structured() {
+    let input = [
+        "{ not json".to_string(),
+        json!({ "jsonrpc": "1.0", "id": 1, "method": "listCommands" }).to_string(),
+        request(2, "no-such-method", json!({})).to_string(),
+        request(3, "rotating-list", json!({ "numbers": [1] })).to_string(),
+        request(4, "schedule", json!({ "input": { "items": "none" } })).to_string(),
+        request(5, "rotating-list", json!({ "input": {} })).to_string(),
+    ]
+    .join("\n");
+    let codes: Vec<(Value, i64)> = session_raw(&input)
+        .into_iter()
+        .map(|response| {
+            (
+                response["id"].clone(),
+                response["error"]["code"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        codes,
+        vec![
+            (json!(1), -32600),
+            (json!(2), -32601),
+            (json!(3), -32602),
+            (json!(4), -32000),
+            (json!(5), -32602),
+            (Value::Null, -32700),
+        ]
+    );
+}
+
+#[test]
+fn cancelling_a_coverage_evaluation() {
+    let numbers: Vec<u32> = (1..=22).collect();
+    let responses = session(&[
+        request(
+            1,
+            "sufficient-coverage-set",
+            json!({ "input": { "items": [{
+                "tickets": [[1, 2, 3]],
+                "target_coverage": { "numbers": numbers, "min_numbers_to_cover": 11 },
+                "expected": false,
+            }] } }),
+        ),
+        json!({ "jsonrpc": "2.0", "method": "$/cancelRequest", "params": { "id": 1 } }),
+    ]);
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["error"]["code"], -32800);
+}
@@ -0,0 +1,126 @@
+/*
+ * The schemas must describe the inputs the commands accept and the results they return: the
+ * documented examples and the fixtures under test/assets have to validate against them.
+ */
+use dat_cli::{command_schema, validate, validate_input_file};
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+
+#[test]
+fn input_examples_match_the_schemas() {
+    for command in dat_cli::registry() {
+        let Some(example) = command.input_example() else {
+            assert!(command.input_schema().is_none(), "{}", command.name());
+            continue;
+        };
+        let example: Value = serde_json::from_str(example).unwrap();
+        let schema = command.input_schema().unwrap();
+        assert_eq!(
+            validate(&schema, &example),
+            Vec::<String>::new(),
+            "{}",
+            command.name()
+        );
+    }
+}
+
+#[test]
+fn fixtures_and_snapshots_match_the_schemas() {
+    let assets = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test")
+        .join("assets");
+    for entry in fs::read_dir(assets).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let Some(stem) = name.strip_suffix(".json") else {
+            continue;
+        };
+        let (stem, output) = match stem.strip_suffix(".expected") {
+            Some(stem) => (stem, true),
+            None => (stem, false),
+        };
+        let command = stem.split('.').next().unwrap().replace('_', "-");
+        let schema = command_schema(&command, output).unwrap();
+        let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(validate(&schema, &value), Vec::<String>::new(), "{}", name);
+    }
+}
+
+#[test]
+fn required_fields_follow_the_serde_defaults() {
+    let schema = command_schema("job-sequencing", false).unwrap();
+    assert_eq!(
+        schema["$defs"]["Job"]["required"],
+        json!(["title", "deadline"])
+    );
+    assert!(command_schema("rotating-list", false).is_err());
+    assert_eq!(
+        command_schema("rotating-list", true).unwrap()["items"]["type"],
+        "integer"
+    );
+}
+
+#[test]
+fn validation_errors_have_paths() {
+    let schema = command_schema("sufficient-coverage-set", false).unwrap();
+    let input = json!({ "items": [
+        { "tickets": [[1, 2]], "target_coverage": { "numbers": [1, 2], "min_numbers_to_cover": 2 }, "expected": true },
+        { "tickets": [[1, -2]], "target_coverage": { "numbers": "1, 2" } },
+    ] });
+    assert_eq!(
+        validate(&schema, &input),
+        vec![
+            "/items/1/tickets/0/1: -2 is less than the minimum of 0",
+            "/items/1/target_coverage/numbers: \"1, 2\" is not of type \"array\"",
+            "/items/1/target_coverage: \"min_numbers_to_cover\" is a required property",
+            "/items/1: \"expected\" is a required property",
+        ]
+    );
+
+    let schedule = command_schema("schedule", true).unwrap();
+    assert!(validate(&schedule, &json!("a chart")).is_empty());
+    assert_eq!(validate(&schedule, &json!(3)).len(), 1);
+}
+
+#[test]
+fn csv_cells_are_validated_as_their_fields() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    // a title that reads as a number is still text
+    let jobs = dir.join("schema_jobs.csv");
+    fs::write(&jobs, "title,deadline,profit\n1,4,10\ntrue,2,5\n").unwrap();
+    assert!(validate_input_file("job-sequencing", &jobs)
+        .unwrap()
+        .is_empty());
+
+    let tasks = dir.join("schema_tasks.csv");
+    fs::write(&tasks, "title,start,end\na,1,x\n").unwrap();
+    let error = validate_input_file("schedule", &tasks).err().unwrap();
+    assert!(
+        error.to_string().contains("in column `end` at line 2"),
+        "{}",
+        error
+    );
+}
+
+fn validate_with_the_cli(command: &str, file: &Path) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args(["schema", command, "--validate"])
+        .arg(file)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn the_cli_reports_files_it_cannot_read() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let missing = validate_with_the_cli("schedule", &dir.join("schema_missing.json"));
+    let malformed_file = dir.join("schema_malformed.json");
+    fs::write(&malformed_file, "{ \"items\": [").unwrap();
+    let malformed = validate_with_the_cli("schedule", &malformed_file);
+    for output in [missing, malformed] {
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.starts_with("error:"), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
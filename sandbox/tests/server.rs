@@ -0,0 +1,145 @@
+/*
+ * Runs the HTTP server on a free localhost port and calls its endpoints over plain TCP.
+ */
+use std::{
+    fs,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    path::Path,
+    thread,
+};
+
+use serde_json::{json, Value};
+
+fn start_server() -> SocketAddr {
+    let server = dat_cli::Server::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr();
+    thread::spawn(move || server.run(2));
+    addr
+}
+
+/// Sends a request and returns the status code and the JSON body of the response
+fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        addr,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn asset(name: &str) -> String {
+    fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test")
+            .join("assets")
+            .join(name),
+    )
+    .unwrap()
+}
+
+#[test]
+fn health_and_listing() {
+    let addr = start_server();
+    let (status, body) = request(addr, "GET", "/health", "");
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = request(addr, "GET", "/v1/commands", "");
+    assert_eq!(status, 200);
+    let names: Vec<&str> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|command| command["name"].as_str().unwrap())
+        .collect();
+    let registered: Vec<&str> = dat_cli::registry()
+        .iter()
+        .map(|command| command.name())
+        .collect();
+    assert_eq!(names, registered);
+}
+
+#[test]
+fn commands_take_the_file_input_as_body() {
+    let addr = start_server();
+    let (status, body) = request(addr, "POST", "/v1/schedule", &asset("schedule.json"));
+    assert_eq!(status, 200);
+    assert_eq!(body["command"], "schedule");
+    let expected: Value = serde_json::from_str(&asset("schedule.expected.json")).unwrap();
+    assert_eq!(body["result"], expected);
+
+    let (status, body) = request(
+        addr,
+        "POST",
+        "/v1/job-sequencing?mode=profit",
+        &asset("job_sequencing.json"),
+    );
+    assert_eq!(status, 200);
+    let expected: Value =
+        serde_json::from_str(&asset("job_sequencing.profit.expected.json")).unwrap();
+    assert_eq!(body["result"], expected);
+}
+
+#[test]
+fn commands_take_their_arguments_as_body() {
+    let addr = start_server();
+    let (status, body) = request(
+        addr,
+        "POST",
+        "/v1/rotating-list",
+        r#"{ "numbers": [1, -2, 3, 4, 5], "k": 2 }"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["result"], json!([4, 5, 1, -2, 3]));
+
+    let (status, body) = request(
+        addr,
+        "POST",
+        "/v1/daily-temperature?mode=Stack",
+        r#"{ "temperatures": [73, 74, 75, 71, 69, 72, 76, 73] }"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["result"], json!([1, 1, 4, 2, 1, 1, 0, 0]));
+}
+
+#[test]
+fn errors_are_reported_as_json() {
+    let addr = start_server();
+    let cases = [
+        ("POST", "/v1/no-such-command", "{}", 404),
+        ("GET", "/v1/schedule", "", 405),
+        ("POST", "/v1/schedule", "{ not json", 400),
+        ("POST", "/v1/rotating-list", r#"{ "numbers": [1, 2] }"#, 400),
+        (
+            "POST",
+            "/v1/daily-temperature?mode=Sideways",
+            r#"{ "temperatures": [1] }"#,
+            400,
+        ),
+        ("POST", "/v1/schedule?json_file_path=/etc/passwd", "{}", 400),
+        ("POST", "/v1/schedule", r#"{ "items": "none" }"#, 422),
+    ];
+    for (method, path, body, expected_status) in cases {
+        let (status, response) = request(addr, method, path, body);
+        assert_eq!(status, expected_status, "{} {}: {}", method, path, response);
+        assert!(
+            response["error"].is_string(),
+            "{} {}: {}",
+            method,
+            path,
+            response
+        );
+    }
+}
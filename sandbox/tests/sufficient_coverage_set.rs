@@ -0,0 +1,61 @@
+/*
+ * The number of numbers each combination has to cover: rejected by the command and the generator
+ * when it is 0 or more than there are numbers, and an empty combination for the algorithm itself.
+ */
+use dat_cli::{evaluate_sufficient_coverage, CommandContext, SufficientCoverageProblemSet};
+use serde_json::json;
+use std::{fs, path::PathBuf, process::Command};
+
+fn problem_set(min_numbers_to_cover: u32) -> serde_json::Value {
+    json!({ "items": [{
+        "tickets": [[1, 2], [2, 3]],
+        "target_coverage": { "numbers": [1, 2, 3], "min_numbers_to_cover": min_numbers_to_cover },
+        "expected": true,
+    }] })
+}
+
+#[test]
+fn the_command_rejects_combinations_of_no_or_too_many_numbers() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("coverage_min_numbers");
+    fs::create_dir_all(&dir).unwrap();
+    for min_numbers_to_cover in [0, 4] {
+        let path = dir.join(format!("{}.json", min_numbers_to_cover));
+        fs::write(&path, problem_set(min_numbers_to_cover).to_string()).unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+            .args(["-q", "sufficient-coverage-set", "-j"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{:?}", output);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("must be between 1 and its 3 numbers"),
+            "{}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn the_generator_rejects_combinations_of_no_numbers() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args([
+            "gen",
+            "sufficient-coverage-set",
+            "--min-numbers-to-cover",
+            "0",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("min_numbers_to_cover of 0"));
+}
+
+#[test]
+fn no_numbers_to_cover_are_covered_by_any_ticket() {
+    let test_set: SufficientCoverageProblemSet = serde_json::from_value(problem_set(0)).unwrap();
+    assert_eq!(
+        evaluate_sufficient_coverage(&test_set, &CommandContext::default()).unwrap(),
+        [true]
+    );
+}
@@ -0,0 +1,148 @@
+/*
+ * Records the events of the algorithms and replays them, the replayed state must end where the
+ * algorithm did.
+ */
+use dat_cli::{CommandContext, ReplayState, TraceEvent, TraceRecorder, Tracer};
+
+/// A context sending the events to the recorder
+fn recording(recorder: &TraceRecorder) -> CommandContext<'_> {
+    CommandContext {
+        tracer: Tracer::new(recorder),
+        ..CommandContext::default()
+    }
+}
+
+fn replay(events: &[TraceEvent]) -> ReplayState {
+    let mut state = ReplayState::default();
+    for event in events {
+        state.apply(event).unwrap();
+    }
+    state
+}
+
+#[test]
+fn tracing_daily_temperature() {
+    let temperatures = [73, 74, 75, 71, 69, 72, 76, 73];
+    for mode in ["Stack", "Reverse"] {
+        let recorder = TraceRecorder::default();
+        let days =
+            dat_cli::get_daily_temperature(&temperatures, mode, &recording(&recorder)).unwrap();
+        let trace = recorder.into_trace("daily-temperature");
+        match replay(&trace.events) {
+            ReplayState::Temperatures { answers, .. } => {
+                let answers: Vec<usize> = answers.iter().map(|a| a.unwrap_or(0)).collect();
+                assert_eq!(answers, days, "{}", mode);
+            }
+            _ => panic!("expected a temperature scan"),
+        }
+    }
+}
+
+#[test]
+fn tracing_a_rotation() {
+    let recorder = TraceRecorder::default();
+    let rotated =
+        dat_cli::rotating_list(vec![1, 2, 3, 4, 5], 2, "right", &recording(&recorder)).unwrap();
+    let trace = recorder.into_trace("rotating-list");
+    assert_eq!(trace.events[1], TraceEvent::Split { at: 3 });
+    assert_eq!(trace.events[2], TraceEvent::Relink { from: 5, to: 1 });
+    match replay(&trace.events) {
+        ReplayState::Rotation { numbers, .. } => assert_eq!(numbers, rotated),
+        _ => panic!("expected a rotation"),
+    }
+}
+
+#[test]
+fn unknown_modes_are_an_error_before_anything_is_traced() {
+    let recorder = TraceRecorder::default();
+    let error = dat_cli::get_daily_temperature(&[73, 74], "Sideways", &recording(&recorder))
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    let error = dat_cli::rotating_list(vec![1, 2], 1, "left", &recording(&recorder))
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(recorder.into_trace("none").events.is_empty());
+}
+
+#[test]
+fn trace_files_round_trip() {
+    let recorder = TraceRecorder::default();
+    dat_cli::rotating_list(vec![1, 2, 3], 1, "right", &recording(&recorder)).unwrap();
+    let trace = recorder.into_trace("rotating-list");
+    let json = serde_json::to_string(&trace).unwrap();
+    assert!(json.contains(r#""event":"relink""#));
+    let read: dat_cli::Trace = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.events, trace.events);
+}
+
+#[test]
+fn replaying_an_edited_trace_fails() {
+    let scan = TraceEvent::TemperatureScan {
+        temperatures: vec![73, 74],
+        mode: "Stack".to_string(),
+    };
+    for event in [
+        TraceEvent::StackPush { day: 2 },
+        TraceEvent::StackPop {
+            day: 5,
+            warmer_day: 6,
+        },
+        TraceEvent::StackPop {
+            day: 1,
+            warmer_day: 0,
+        },
+        TraceEvent::Answer { day: 9, days: 1 },
+    ] {
+        let mut state = replay(std::slice::from_ref(&scan));
+        let error = state.apply(&event).unwrap_err();
+        assert!(error.to_string().contains("does not fit"), "{}", error);
+    }
+
+    let mut state = replay(&[TraceEvent::ListRotation {
+        numbers: vec![1, 2, 3],
+        k: 1,
+    }]);
+    assert!(state.apply(&TraceEvent::Split { at: 4 }).is_err());
+}
+
+#[test]
+fn replay_reports_an_edited_trace_file() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("edited_trace.json");
+    std::fs::write(
+        &path,
+        r#"{ "command": "rotating-list", "events": [
+            { "event": "list_rotation", "numbers": [1, 2, 3], "k": 1 },
+            { "event": "split", "at": 7 }
+        ] }"#,
+    )
+    .unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .arg("replay")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("error:") && stderr.contains("event 2"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn the_trace_flag_goes_after_the_subcommand_too() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("trailing_flag_trace.json");
+    let _ = std::fs::remove_file(&path);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_dat_cli"))
+        .args(["-q", "rotating-list", "-n", "1,2,3", "-k", "1", "--trace"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let trace: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(trace["command"], "rotating-list");
+}
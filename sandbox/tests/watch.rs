@@ -0,0 +1,84 @@
+/*
+ * Watch mode: the command runs again on every change of its input file, and only the difference
+ * is printed.
+ */
+use std::{fs, path::PathBuf, thread, time::Duration};
+
+use dat_cli::{diff_lines, parse_command, track_allocations, watch_command, CancellationToken};
+
+/// Longer than the poll interval and the debounce together, so every edit gets its own run
+const SETTLE: Duration = Duration::from_millis(1200);
+
+#[test]
+fn diff_lines_shows_changed_lines_in_hunks() {
+    let diff = diff_lines("a\nb\nc\nd", "a\nx\nc\nd\ne");
+    assert_eq!(
+        diff,
+        vec!["@@ -2 +2 @@", "- b", "+ x", "@@ -5 +5 @@", "+ e"]
+    );
+    assert!(diff_lines("same", "same").is_empty());
+}
+
+#[test]
+fn diff_lines_replaces_everything_on_large_texts() {
+    // 4,000 lines on each side is above the limit, the table would take over 100 MiB
+    let old: Vec<String> = (0..4_000).map(|i| format!("line {}", i)).collect();
+    let mut new = old.clone();
+    new[0] = "changed".to_string();
+    let (diff, stats) = track_allocations(|| diff_lines(&old.join("\n"), &new.join("\n")));
+    assert_eq!(diff.len(), 1 + 4_000 + 4_000);
+    assert_eq!(diff[0], "@@ -1 +1 @@");
+    assert_eq!(diff[1], "- line 0");
+    assert_eq!(diff[4_001], "+ changed");
+    // only tracked with the track-allocations feature
+    if let Some(stats) = stats {
+        assert!(stats.peak_bytes < 16 << 20, "{}", stats);
+    }
+}
+
+#[test]
+fn reruns_on_changes_and_survives_broken_input() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let path = dir.join("watch_schedule.json");
+    fs::write(
+        &path,
+        r#"{ "items": [ { "title": "a", "start": 0, "end": 10 } ] }"#,
+    )
+    .unwrap();
+    let invocation =
+        parse_command(["schedule", "--json-file-path", path.to_str().unwrap()]).unwrap();
+
+    let stop = CancellationToken::new();
+    let editor = {
+        let stop = stop.clone();
+        let path = path.clone();
+        thread::spawn(move || {
+            thread::sleep(SETTLE);
+            fs::write(
+                &path,
+                r#"{ "items": [ { "title": "b", "start": 0, "end": 10 } ] }"#,
+            )
+            .unwrap();
+            thread::sleep(SETTLE);
+            fs::write(&path, "{ broken").unwrap();
+            thread::sleep(SETTLE);
+            stop.cancel();
+        })
+    };
+    let mut output = Vec::new();
+    watch_command(&invocation, &mut output, &stop).unwrap();
+    editor.join().unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(r#""title": "a""#), "{}", output);
+    assert!(output.contains(r#"-       "title": "a""#), "{}", output);
+    assert!(output.contains(r#"+       "title": "b""#), "{}", output);
+    assert!(output.contains("error: "), "{}", output);
+}
+
+#[test]
+fn needs_an_input_file() {
+    let invocation = parse_command(["rotating-list", "-n", "1,2", "-k", "1"]).unwrap();
+    let result = watch_command(&invocation, &mut Vec::new(), &CancellationToken::new());
+    assert!(result.is_err());
+}